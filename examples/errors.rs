@@ -9,6 +9,7 @@ struct Data {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[allow(dead_code)]
 struct Empty;
 
 fn main() {
@@ -0,0 +1,243 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::client::{Client, Meta, SIZE_LIMIT};
+use crate::error::{Error, Result};
+
+/// Options of `Client::create_bulk_chunked`.
+///
+/// By default a chunk holds at most 100 records and 10KB of JSON, and chunks are sent one by one.
+#[derive(Clone, Debug)]
+pub struct ChunkOptions {
+    max_records: usize,
+    max_bytes: usize,
+    concurrency: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> ChunkOptions {
+        ChunkOptions {
+            max_records: 100,
+            max_bytes: SIZE_LIMIT,
+            concurrency: 1,
+        }
+    }
+}
+
+impl ChunkOptions {
+    pub fn new() -> ChunkOptions {
+        ChunkOptions::default()
+    }
+
+    /// Set the maximum number of records in a chunk.
+    pub fn max_records(mut self, max_records: usize) -> ChunkOptions {
+        self.max_records = max_records.max(1);
+        self
+    }
+
+    /// Set the maximum size of a chunk as serialized JSON, in bytes.
+    ///
+    /// A record larger than this is sent alone in its own chunk.
+    pub fn max_bytes(mut self, max_bytes: usize) -> ChunkOptions {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Set how many chunks are sent in parallel.
+    pub fn concurrency(mut self, concurrency: usize) -> ChunkOptions {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+}
+
+/// Result of a chunk sent by `Client::create_bulk_chunked`.
+#[derive(Debug)]
+pub struct ChunkResult<T> {
+    /// Indices of the chunk's records in the input.
+    pub range: Range<usize>,
+    pub result: Result<Vec<(T, Meta)>>,
+}
+
+/// Report of `Client::update_many` and `Client::delete_many`.
+#[derive(Debug, Default)]
+pub struct BulkReport {
+    /// Ids of records processed successfully, in input order.
+    pub succeeded: Vec<String>,
+    /// Ids of records that failed with their errors, in input order.
+    pub failed: Vec<(String, Error)>,
+}
+
+impl BulkReport {
+    /// Whether all records were processed successfully.
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    pub(crate) fn collect(results: Vec<(String, Result<()>)>) -> BulkReport {
+        let mut report = BulkReport::default();
+        for (id, result) in results {
+            match result {
+                Ok(()) => report.succeeded.push(id),
+                Err(err) => report.failed.push((id, err)),
+            }
+        }
+        report
+    }
+}
+
+impl<'a> Client<'a> {
+    /// Update many records, sending at most `concurrency` requests in parallel.
+    ///
+    /// Unlike a loop over `update`, a failure doesn't stop the other updates. Every failure is
+    /// reported with its id in the returned `BulkReport`.
+    pub fn update_many<I, S, T>(&self, items: I, concurrency: usize) -> BulkReport
+    where
+        I: IntoIterator<Item = (S, T)>,
+        S: AsRef<str> + Send,
+        T: Serialize + Send,
+    {
+        let items: Vec<(S, T)> = items.into_iter().collect();
+        let results = parallel(items, concurrency, |(id, data)| {
+            let result = self.update(id.as_ref(), &data);
+            (id.as_ref().to_string(), result)
+        });
+        BulkReport::collect(results)
+    }
+
+    /// Delete many records, sending at most `concurrency` requests in parallel.
+    ///
+    /// Unlike a loop over `delete`, a failure doesn't stop the other deletions. Every failure is
+    /// reported with its id in the returned `BulkReport`.
+    pub fn delete_many<I, S>(&self, ids: I, concurrency: usize) -> BulkReport
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str> + Send,
+    {
+        let ids: Vec<S> = ids.into_iter().collect();
+        let results = parallel(ids, concurrency, |id| {
+            let result = self.delete(id.as_ref());
+            (id.as_ref().to_string(), result)
+        });
+        BulkReport::collect(results)
+    }
+
+    /// Create records in chunks, keeping each request under jsonbox's limits.
+    ///
+    /// Records are split into chunks by count and serialized size as configured in `options`,
+    /// and every chunk is sent with a bulk `CREATE`. A failed chunk doesn't stop the others:
+    /// results are returned per chunk in input order, so failed ranges can be retried.
+    pub fn create_bulk_chunked<T>(
+        &self,
+        data: &[T],
+        options: &ChunkOptions,
+    ) -> Result<Vec<ChunkResult<T>>>
+    where
+        T: Serialize + DeserializeOwned + Send,
+    {
+        let records = self.payloads(data)?;
+        let sizes: Vec<usize> = records.iter().map(|record| record.len()).collect();
+
+        let results = parallel(chunks(&sizes, options), options.concurrency, |range| {
+            let body = format!("[{}]", records[range.clone()].join(","));
+            let result = self.create_bulk_raw(body);
+            ChunkResult { range, result }
+        });
+        Ok(results)
+    }
+}
+
+/// Split records of given serialized sizes into ranges satisfying `options`.
+pub(in crate::client) fn chunks(sizes: &[usize], options: &ChunkOptions) -> Vec<Range<usize>> {
+    let mut chunks = vec![];
+    let mut start = 0;
+    // Size of `[]`, plus a record and a comma for each record.
+    let mut bytes = 2;
+    for (i, size) in sizes.iter().enumerate() {
+        let count = i - start;
+        let grown = bytes + size + if count > 0 { 1 } else { 0 };
+        if count > 0 && (count >= options.max_records || grown > options.max_bytes) {
+            chunks.push(start..i);
+            start = i;
+            bytes = 2 + size;
+        } else {
+            bytes = grown;
+        }
+    }
+    if start < sizes.len() {
+        chunks.push(start..sizes.len());
+    }
+    chunks
+}
+
+/// Run `f` on each job with at most `concurrency` threads, returning results in job order.
+pub(crate) fn parallel<J, R, F>(jobs: Vec<J>, concurrency: usize, f: F) -> Vec<R>
+where
+    J: Send,
+    R: Send,
+    F: Fn(J) -> R + Sync,
+{
+    if concurrency <= 1 || jobs.len() <= 1 {
+        return jobs.into_iter().map(f).collect();
+    }
+
+    let count = jobs.len();
+    let jobs: Vec<Mutex<Option<J>>> = jobs.into_iter().map(|job| Mutex::new(Some(job))).collect();
+    let results: Vec<Mutex<Option<R>>> = (0..count).map(|_| Mutex::new(None)).collect();
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..concurrency.min(count) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= count {
+                    break;
+                }
+                let job = jobs[i].lock().unwrap().take().unwrap();
+                let result = f(job);
+                *results[i].lock().unwrap() = Some(result);
+            });
+        }
+    });
+    results
+        .into_iter()
+        .map(|result| result.into_inner().unwrap().unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_by_records() {
+        let options = ChunkOptions::new().max_records(2);
+        assert_eq!(
+            chunks(&[10, 10, 10, 10, 10], &options),
+            vec![0..2, 2..4, 4..5]
+        );
+        assert_eq!(chunks(&[], &options), Vec::<Range<usize>>::new());
+    }
+
+    #[test]
+    fn test_chunks_by_bytes() {
+        // [a,b] = 2 + 10 + 1 + 10 = 23 bytes
+        let options = ChunkOptions::new().max_bytes(23);
+        assert_eq!(chunks(&[10, 10, 10], &options), vec![0..2, 2..3]);
+
+        // Oversized records go alone.
+        let options = ChunkOptions::new().max_bytes(20);
+        assert_eq!(chunks(&[5, 50, 5, 5], &options), vec![0..1, 1..2, 2..4]);
+    }
+
+    #[test]
+    fn test_parallel() {
+        let jobs: Vec<u64> = (0..20).collect();
+        let results = parallel(jobs, 4, |n| {
+            thread::sleep(std::time::Duration::from_millis(20 - n));
+            n * 2
+        });
+        assert_eq!(results, (0..20).map(|n| n * 2).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::client::Client;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Key {
+    Id(String),
+    Query(String),
+}
+
+struct Entry {
+    body: String,
+    expires: Instant,
+    used: u64,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<Key, Entry>,
+    tick: u64,
+    /// Bumped by every invalidation, to tell whether a response may be stale.
+    generation: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+/// An in-memory LRU cache of raw response bodies, enabled by `Client::with_cache()`.
+pub(crate) struct Cache {
+    capacity: usize,
+    ttl: Duration,
+    state: Mutex<State>,
+}
+
+impl Cache {
+    pub(crate) fn new(capacity: usize, ttl: Duration) -> Cache {
+        Cache {
+            capacity,
+            ttl,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &Key) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let now = Instant::now();
+        match state.entries.get_mut(key) {
+            Some(entry) if entry.expires > now => {
+                entry.used = tick;
+                let body = entry.body.clone();
+                state.hits += 1;
+                Some(body)
+            }
+            Some(_) => {
+                state.entries.remove(key);
+                state.misses += 1;
+                None
+            }
+            None => {
+                state.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Current generation, to pass to `put()` once the response of a request sent now arrives.
+    pub(crate) fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
+    }
+
+    /// Store a response, unless the cache was invalidated since `generation`: a write may have
+    /// happened while the request was in flight, so the response may be stale.
+    pub(crate) fn put(&self, key: Key, body: String, generation: u64) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.generation != generation {
+            return;
+        }
+        state.tick += 1;
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.capacity {
+            let now = Instant::now();
+            state.entries.retain(|_, entry| entry.expires > now);
+            if state.entries.len() >= self.capacity {
+                let lru = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.used)
+                    .map(|(key, _)| key.clone());
+                if let Some(lru) = lru {
+                    state.entries.remove(&lru);
+                    state.evictions += 1;
+                }
+            }
+        }
+        let entry = Entry {
+            body,
+            expires: Instant::now() + self.ttl,
+            used: state.tick,
+        };
+        state.entries.insert(key, entry);
+    }
+
+    /// Drop all query results, and the record `id` if given.
+    pub(crate) fn invalidate(&self, id: Option<&str>) {
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        state.entries.retain(|key, _| match key {
+            Key::Id(cached) => Some(cached.as_str()) != id,
+            Key::Query(_) => false,
+        });
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        let state = self.state.lock().unwrap();
+        CacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            evictions: state.evictions,
+            entries: state.entries.len(),
+        }
+    }
+}
+
+/// Statistics of the read-through cache. Use `Client::cache_stats()` to get it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Number of live entries dropped to make room for new ones.
+    pub evictions: u64,
+    /// Number of entries currently held, including expired ones not dropped yet.
+    pub entries: usize,
+}
+
+impl<'a> Client<'a> {
+    /// Cache responses of reads by id and by query in memory, for `ttl` each.
+    ///
+    /// Up to `capacity` responses are kept, the least recently used one being dropped first.
+    /// Writes through this client drop affected entries: the record itself on `update` and
+    /// `delete`, and every query result on any write. A response to a read sent before such a
+    /// write isn't cached, as it may predate it. Writes by other clients aren't seen
+    /// until entries expire. `update_if_unmodified` and `modify` always read from the server.
+    pub fn with_cache(mut self, capacity: usize, ttl: Duration) -> Client<'a> {
+        self.cache = Some(Cache::new(capacity, ttl));
+        self
+    }
+
+    /// Get statistics of the cache, if enabled by `with_cache()`.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
+    }
+
+    pub(crate) fn cache_get(&self, key: &Key) -> Option<String> {
+        self.cache.as_ref().and_then(|cache| cache.get(key))
+    }
+
+    pub(crate) fn cache_generation(&self) -> u64 {
+        self.cache.as_ref().map_or(0, |cache| cache.generation())
+    }
+
+    pub(crate) fn cache_put(&self, key: Key, body: &str, generation: u64) {
+        if let Some(cache) = &self.cache {
+            cache.put(key, body.to_string(), generation);
+        }
+    }
+
+    pub(crate) fn cache_invalidate(&self, id: Option<&str>) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(id: &str) -> Key {
+        Key::Id(id.into())
+    }
+
+    #[test]
+    fn test_lru() {
+        let cache = Cache::new(2, Duration::from_secs(60));
+        cache.put(id("a"), "A".into(), 0);
+        cache.put(id("b"), "B".into(), 0);
+        assert_eq!(cache.get(&id("a")), Some("A".into()));
+        cache.put(id("c"), "C".into(), 0);
+
+        assert_eq!(cache.get(&id("b")), None);
+        assert_eq!(cache.get(&id("a")), Some("A".into()));
+        assert_eq!(cache.get(&id("c")), Some("C".into()));
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 3,
+                misses: 1,
+                evictions: 1,
+                entries: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_ttl() {
+        let cache = Cache::new(2, Duration::from_millis(0));
+        cache.put(id("a"), "A".into(), 0);
+        assert_eq!(cache.get(&id("a")), None);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let cache = Cache::new(4, Duration::from_secs(60));
+        cache.put(id("a"), "A".into(), 0);
+        cache.put(id("b"), "B".into(), 0);
+        cache.put(Key::Query("limit=1".into()), "[]".into(), 0);
+        cache.invalidate(Some("a"));
+        assert_eq!(cache.get(&id("a")), None);
+        assert_eq!(cache.get(&id("b")), Some("B".into()));
+        assert_eq!(cache.get(&Key::Query("limit=1".into())), None);
+    }
+
+    #[test]
+    fn test_put_after_invalidate() {
+        let cache = Cache::new(4, Duration::from_secs(60));
+        let generation = cache.generation();
+        cache.invalidate(Some("a"));
+        cache.put(id("a"), "stale".into(), generation);
+        assert_eq!(cache.get(&id("a")), None);
+
+        cache.put(id("a"), "A".into(), cache.generation());
+        assert_eq!(cache.get(&id("a")), Some("A".into()));
+    }
+}
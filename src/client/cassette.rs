@@ -0,0 +1,277 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::error::{self, Error, Result};
+use crate::{Middleware, Next, Request, Response};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct RecordedRequest {
+    method: String,
+    path: String,
+    #[serde(default)]
+    query: Option<String>,
+    #[serde(default)]
+    body: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct RecordedResponse {
+    status: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Tape {
+    interactions: Vec<Interaction>,
+}
+
+enum Mode {
+    Record,
+    Replay { played: Vec<bool> },
+}
+
+struct State {
+    mode: Mode,
+    tape: Tape,
+}
+
+/// A middleware recording requests and responses to a file, or serving them back from it.
+///
+/// In record mode requests are sent to the server as usual and every request/response pair is
+/// appended to the cassette, which is written by `save()`. In replay mode no request reaches
+/// the server: each request is answered with the first recorded response not played yet whose
+/// method, path and query string match, so the same request can be replayed several times with
+/// different results in the order they were recorded.
+///
+/// `Cassette` is a handle, clones share the same tape.
+///
+/// ```ignore
+/// let cassette = Cassette::record("tests/cassettes/crud.json");
+/// let client = Client::new("<BOX_ID>").with_middleware(cassette.clone());
+/// // ... run operations against the real service ...
+/// cassette.save()?;
+///
+/// let cassette = Cassette::replay("tests/cassettes/crud.json")?;
+/// let client = Client::new("<BOX_ID>").with_middleware(cassette);
+/// ```
+#[derive(Clone)]
+pub struct Cassette {
+    path: PathBuf,
+    state: Arc<Mutex<State>>,
+}
+
+impl Cassette {
+    /// Start recording a new cassette, which will be written to `path`.
+    pub fn record<P: AsRef<Path>>(path: P) -> Cassette {
+        Cassette::new(path, Mode::Record, Tape::default())
+    }
+
+    /// Load a recorded cassette from `path` to replay it.
+    pub fn replay<P: AsRef<Path>>(path: P) -> Result<Cassette> {
+        let file = File::open(path.as_ref()).context(error::Io {})?;
+        let tape: Tape = serde_json::from_reader(BufReader::new(file))
+            .context(error::Json { reason: "cassette" })?;
+        let played = vec![false; tape.interactions.len()];
+        Ok(Cassette::new(path, Mode::Replay { played }, tape))
+    }
+
+    fn new<P: AsRef<Path>>(path: P, mode: Mode, tape: Tape) -> Cassette {
+        Cassette {
+            path: path.as_ref().to_path_buf(),
+            state: Arc::new(Mutex::new(State { mode, tape })),
+        }
+    }
+
+    /// Write recorded interactions to the cassette file.
+    pub fn save(&self) -> Result<()> {
+        let state = self.state.lock().unwrap();
+        let file = File::create(&self.path).context(error::Io {})?;
+        serde_json::to_writer_pretty(file, &state.tape).context(error::Json { reason: "cassette" })
+    }
+
+    /// Number of interactions on the tape.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().tape.interactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Middleware for Cassette {
+    fn handle(&self, req: Request, next: Next) -> Result<Response> {
+        let recorded = recorded_request(&req)?;
+
+        if let State {
+            mode: Mode::Replay { played },
+            tape,
+        } = &mut *self.state.lock().unwrap()
+        {
+            let found = tape.interactions.iter().enumerate().position(|(i, it)| {
+                !played[i]
+                    && it.request.method == recorded.method
+                    && it.request.path == recorded.path
+                    && it.request.query == recorded.query
+            });
+            return match found {
+                Some(i) => {
+                    played[i] = true;
+                    Ok(response_of(&tape.interactions[i].response))
+                }
+                None => Err(Error::Cassette {
+                    message: format!(
+                        "no recorded interaction for {} {}{}",
+                        recorded.method,
+                        recorded.path,
+                        recorded
+                            .query
+                            .map(|q| format!("?{}", q))
+                            .unwrap_or_default()
+                    ),
+                }),
+            };
+        }
+
+        let res = next.run(req)?;
+        let headers = res
+            .headers
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (name.as_str().to_string(), value.to_string()))
+            })
+            .collect();
+        let interaction = Interaction {
+            request: recorded,
+            response: RecordedResponse {
+                status: res.status,
+                headers,
+                body: res.body.clone(),
+            },
+        };
+        self.state
+            .lock()
+            .unwrap()
+            .tape
+            .interactions
+            .push(interaction);
+        Ok(res)
+    }
+}
+
+fn recorded_request(req: &Request) -> Result<RecordedRequest> {
+    let url = Url::parse(&req.url).map_err(|err| Error::Cassette {
+        message: format!("invalid URL {}: {}", req.url, err),
+    })?;
+    Ok(RecordedRequest {
+        method: req.method.as_str().to_string(),
+        path: url.path().to_string(),
+        query: url.query().map(|q| q.to_string()),
+        body: req.body.clone(),
+    })
+}
+
+fn response_of(recorded: &RecordedResponse) -> Response {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &recorded.headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+    Response {
+        status: recorded.status,
+        headers,
+        body: recorded.body.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Method;
+
+    fn request(method: Method, url: &str) -> Request {
+        Request {
+            method,
+            url: url.into(),
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    fn interaction(method: &str, path: &str, query: Option<&str>, body: &str) -> Interaction {
+        Interaction {
+            request: RecordedRequest {
+                method: method.into(),
+                path: path.into(),
+                query: query.map(|q| q.into()),
+                body: None,
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers: BTreeMap::new(),
+                body: body.into(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_recorded_request() {
+        let req = request(
+            Method::GET,
+            "https://jsonbox.io/xxx?sort=name&skip=0&limit=20",
+        );
+        let recorded = recorded_request(&req).unwrap();
+        assert_eq!(recorded.method, "GET");
+        assert_eq!(recorded.path, "/xxx");
+        assert_eq!(recorded.query, Some("sort=name&skip=0&limit=20".into()));
+    }
+
+    #[test]
+    fn test_replay_in_order() {
+        let tape = Tape {
+            interactions: vec![
+                interaction("GET", "/xxx/1", None, "first"),
+                interaction("GET", "/xxx", Some("limit=1"), "query"),
+                interaction("GET", "/xxx/1", None, "second"),
+            ],
+        };
+        let cassette = Cassette::new(
+            "unused",
+            Mode::Replay {
+                played: vec![false; 3],
+            },
+            tape,
+        );
+        let transport = |_: Request| -> Result<Response> { panic!("must not be called") };
+        let play =
+            |url: &str| cassette.handle(request(Method::GET, url), Next::new(&[], &transport));
+
+        assert_eq!(play("http://a/xxx/1").unwrap().body, "first");
+        assert_eq!(play("http://b/xxx/1").unwrap().body, "second");
+        assert_eq!(play("http://a/xxx?limit=1").unwrap().body, "query");
+        assert!(play("http://a/xxx/1").is_err());
+        assert!(play("http://a/xxx?limit=2").is_err());
+    }
+}
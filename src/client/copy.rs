@@ -0,0 +1,181 @@
+use serde_json::{json, Value};
+
+use crate::client::bulk::{chunks, ChunkOptions};
+use crate::client::migrate::Resume;
+use crate::client::Client;
+use crate::error::{Error, Result};
+
+/// Options of `Client::copy_to()`.
+#[derive(Clone, Debug)]
+pub struct CopyOptions {
+    page_size: u32,
+    preserve_meta: Option<String>,
+    verify: bool,
+    resume_from: Option<String>,
+}
+
+impl Default for CopyOptions {
+    fn default() -> CopyOptions {
+        CopyOptions {
+            page_size: 100,
+            preserve_meta: None,
+            verify: true,
+            resume_from: None,
+        }
+    }
+}
+
+impl CopyOptions {
+    pub fn new() -> CopyOptions {
+        CopyOptions::default()
+    }
+
+    /// Number of records read per request. Default is 100.
+    pub fn page_size(mut self, page_size: u32) -> CopyOptions {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Keep the original `_id`, `_createdOn` and `_updatedOn` of each record in a payload field,
+    /// as `{"id": ..., "created_on": ..., "updated_on": ...}`. jsonbox assigns new ones on create.
+    pub fn preserve_meta(mut self, field: &str) -> CopyOptions {
+        self.preserve_meta = Some(field.to_string());
+        self
+    }
+
+    /// Whether to count records of the target box before and after copying. Default is `true`.
+    ///
+    /// Counting reads every record of the target box, so it's read twice in full on top of
+    /// the copy. Turn this off for large target boxes.
+    pub fn verify(mut self, verify: bool) -> CopyOptions {
+        self.verify = verify;
+        self
+    }
+
+    /// Skip records up to and including the one with this original id, which is usually the
+    /// checkpoint of an interrupted copy. If no record has this id, `Error::CheckpointNotFound`
+    /// is returned after scanning without copying anything.
+    pub fn resume_from(mut self, id: &str) -> CopyOptions {
+        self.resume_from = Some(id.to_string());
+        self
+    }
+}
+
+/// Result of `Client::copy_to()`.
+#[derive(Debug, Default)]
+pub struct CopyReport {
+    /// Number of records copied to the target box, not counting skipped ones.
+    pub read: usize,
+    /// Original id and new id of each record created in the target box, in copy order.
+    pub ids: Vec<(String, String)>,
+}
+
+impl<'a> Client<'a> {
+    /// Copy all records of this box to the box of `target`, which may be on another host.
+    ///
+    /// Records are read page by page in creation order and recreated with bulk creates, split
+    /// to stay under jsonbox's limits. If a write fails, `Error::Interrupted` is returned with
+    /// the original id of the last record copied, to be passed to `CopyOptions::resume_from`.
+    /// With verification on, the target box must have grown by the number of records copied,
+    /// or `Error::CountMismatch` is returned.
+    pub fn copy_to(&self, target: &Client, options: &CopyOptions) -> Result<CopyReport> {
+        if let Some(field) = &options.preserve_meta {
+            if field.starts_with('_') || field.starts_with('$') {
+                return Err(Error::ReservedKey {
+                    paths: vec![format!("/{}", field)],
+                });
+            }
+        }
+        let before = if options.verify {
+            Some(target.count()?)
+        } else {
+            None
+        };
+
+        let mut report = CopyReport::default();
+        let mut resume = Resume::new(options.resume_from.as_deref());
+        if let Err(err) = self.copy_pages(target, options, &mut resume, &mut report) {
+            let checkpoint = match report.ids.last() {
+                Some((id, _)) => Some(id.clone()),
+                None => options.resume_from.clone(),
+            };
+            return Err(Error::Interrupted {
+                checkpoint,
+                source: Box::new(err),
+            });
+        }
+        resume.finish()?;
+
+        if let Some(before) = before {
+            let after = target.count()?;
+            if after != before + report.read {
+                return Err(Error::CountMismatch {
+                    expected: before + report.read,
+                    actual: after,
+                });
+            }
+        }
+        Ok(report)
+    }
+
+    fn copy_pages(
+        &self,
+        target: &Client,
+        options: &CopyOptions,
+        resume: &mut Resume,
+        report: &mut CopyReport,
+    ) -> Result<()> {
+        for page in self
+            .read()
+            .order_by("_createdOn")
+            .limit(options.page_size)
+            .pages()
+        {
+            let mut page = page?;
+            page.retain(|(_, meta)| !resume.skip(&meta.id));
+            if page.is_empty() {
+                continue;
+            }
+            let (records, ids): (Vec<Value>, Vec<String>) = page
+                .into_iter()
+                .map(|(mut record, meta)| {
+                    if let (Some(field), Value::Object(map)) = (&options.preserve_meta, &mut record)
+                    {
+                        map.insert(
+                            field.clone(),
+                            json!({
+                                "id": meta.id,
+                                "created_on": meta.created_on,
+                                "updated_on": meta.updated_on,
+                            }),
+                        );
+                    }
+                    (record, meta.id)
+                })
+                .unzip();
+
+            // Chunks are sent one by one to stop at the first failure.
+            let bodies = target.payloads(&records)?;
+            let sizes: Vec<usize> = bodies.iter().map(|body| body.len()).collect();
+            for range in chunks(&sizes, &ChunkOptions::new()) {
+                let body = format!("[{}]", bodies[range.clone()].join(","));
+                let created = target.create_bulk_raw::<Value>(body)?;
+                report.read += range.len();
+                for (i, (_, meta)) in range.zip(created) {
+                    report.ids.push((ids[i].clone(), meta.id));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Count all records of the box.
+    fn count(&self) -> Result<usize> {
+        let mut count = 0;
+        for page in self.read().limit(1000).pages() {
+            count += page?.len();
+        }
+        Ok(count)
+    }
+}
@@ -0,0 +1,269 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{from_slice, from_str, to_string, to_vec, Value};
+use snafu::ResultExt;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::client::query_builder::MAX_LIMIT;
+use crate::client::Meta;
+use crate::error::{self, Error, Result};
+use crate::QueryBuilder;
+
+/// Position after the last record of a page, for `QueryBuilder::page_after()`.
+///
+/// Its string form is opaque and URL-safe, so it can be handed to clients of another API
+/// and parsed back with `str::parse()`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Cursor {
+    field: String,
+    value: Value,
+    id: String,
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let json = to_vec(self).map_err(|_| fmt::Error)?;
+        for byte in json {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Cursor> {
+        let invalid = || Error::InvalidCursor {
+            message: "malformed cursor".to_string(),
+        };
+        if s.len() % 2 != 0 || !s.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| invalid()))
+            .collect::<Result<Vec<u8>>>()?;
+        from_slice(&bytes).map_err(|_| invalid())
+    }
+}
+
+/// A page of records fetched by `QueryBuilder::page_after()`.
+#[derive(Debug)]
+pub struct Page<T> {
+    pub records: Vec<(T, Meta)>,
+    /// Cursor to fetch the next page, or `None` if this is the last one.
+    pub next: Option<Cursor>,
+}
+
+impl<'a> QueryBuilder<'a> {
+    /// Get the page of records following `cursor`, or the first page if `None`.
+    ///
+    /// Unlike `skip`, a cursor isn't thrown off by records created or deleted between pages.
+    /// Pages are fetched with a `>=` (or `<=` in descending order) filter on the sort field,
+    /// so the field must support jsonbox's range filters, such as a number. Records with the
+    /// same sort value are ordered by `_id`; more records are fetched when needed to get past
+    /// a run of equal values. The configured `skip` is ignored.
+    pub fn page_after<T>(&self, cursor: Option<&Cursor>) -> Result<Page<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let (field, desc) = self.sort_key();
+        let limit = self.page_size().clamp(1, MAX_LIMIT);
+
+        let mut base = self.clone();
+        base.skip(0);
+        if let Some(cursor) = cursor {
+            if cursor.field != field {
+                return Err(Error::InvalidCursor {
+                    message: format!("cursor of '{}' used to sort by '{}'", cursor.field, field),
+                });
+            }
+            let op = if desc { "<=" } else { ">=" };
+            base.filter_by(
+                &format!("{}:{}{{}}", field, op),
+                filter_value(&cursor.value),
+            );
+        }
+
+        // Fetch one more record than needed, to tell whether a run of equal values is cut.
+        let mut fetch = (limit + 1).min(MAX_LIMIT);
+        loop {
+            let mut query = base.clone();
+            query.limit(fetch);
+            let client = query.client;
+            let page = client.read_by_query_with(&query, |raw| {
+                let records: Vec<Value> = from_str(raw).context(error::Json { reason: "data" })?;
+                Ok(select(records, field, desc, cursor, limit, fetch))
+            })?;
+            match page {
+                Some((records, next)) => {
+                    let raw = to_string(&records).context(error::Json { reason: "data" })?;
+                    let records = client.decode_records(&raw)?;
+                    return Ok(Page { records, next });
+                }
+                None if fetch < MAX_LIMIT => fetch = (fetch * 2).min(MAX_LIMIT),
+                None => {
+                    return Err(Error::InvalidCursor {
+                        message: format!(
+                            "more than {} records share a value of '{}'",
+                            fetch, field
+                        ),
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// Pick records of a page from fetched ones, returning `None` if more must be fetched.
+fn select(
+    mut records: Vec<Value>,
+    field: &str,
+    desc: bool,
+    cursor: Option<&Cursor>,
+    limit: u32,
+    fetch: u32,
+) -> Option<(Vec<Value>, Option<Cursor>)> {
+    let full = records.len() as u32 >= fetch;
+    let order = |a: &Value, b: &Value| {
+        let ord = compare(&a[field], &b[field]);
+        let ord = if desc { ord.reverse() } else { ord };
+        ord.then_with(|| compare(&a["_id"], &b["_id"]))
+    };
+    records.sort_by(|a, b| order(a, b));
+
+    if let Some(cursor) = cursor {
+        let last = serde_json::json!({ field: cursor.value, "_id": cursor.id });
+        records.retain(|r| order(r, &last) == Ordering::Greater);
+    }
+
+    // When more records may follow, the run of values at the end may be incomplete.
+    if full {
+        let edge = records
+            .last()
+            .map(|r| r[field].clone())
+            .unwrap_or(Value::Null);
+        records.retain(|r| compare(&r[field], &edge) != Ordering::Equal);
+        if records.is_empty() {
+            return None;
+        }
+    }
+
+    let more = full || records.len() as u32 > limit;
+    records.truncate(limit as usize);
+
+    let next = match records.last() {
+        Some(last) if more => Some(Cursor {
+            field: field.to_string(),
+            value: last[field].clone(),
+            id: last["_id"].as_str().unwrap_or_default().to_string(),
+        }),
+        _ => None,
+    };
+    Some((records, next))
+}
+
+/// Order JSON values as jsonbox sorts them: nulls, numbers, strings, then others.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Number(_) => 1,
+            Value::String(_) => 2,
+            _ => 3,
+        }
+    }
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn filter_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn records(values: &[(i64, &str)]) -> Vec<Value> {
+        values
+            .iter()
+            .map(|(n, id)| json!({ "n": n, "_id": id }))
+            .collect()
+    }
+
+    fn ids(records: &[Value]) -> Vec<&str> {
+        records.iter().map(|r| r["_id"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_cursor_string() {
+        let cursor = Cursor {
+            field: "n".into(),
+            value: json!(2),
+            id: "b".into(),
+        };
+        let s = cursor.to_string();
+        assert!(s.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(s.parse::<Cursor>().unwrap(), cursor);
+        assert!("xyz".parse::<Cursor>().is_err());
+    }
+
+    #[test]
+    fn test_select_after_cursor() {
+        // Fetched 4 of more: the trailing run of 3 may be incomplete, so it's left for the next page.
+        let fetched = records(&[(1, "a"), (2, "c"), (2, "b"), (3, "d")]);
+        let (page, next) = select(fetched, "n", false, None, 3, 4).unwrap();
+        assert_eq!(ids(&page), vec!["a", "b", "c"]);
+        let next = next.unwrap();
+        assert_eq!((next.value.clone(), next.id.as_str()), (json!(2), "c"));
+
+        let fetched = records(&[(2, "b"), (2, "c"), (3, "d")]);
+        let (page, next) = select(fetched, "n", false, Some(&next), 3, 4).unwrap();
+        assert_eq!(ids(&page), vec!["d"]);
+        assert!(next.is_none());
+    }
+
+    #[test]
+    fn test_select_desc() {
+        let fetched = records(&[(1, "a"), (3, "d"), (2, "b"), (2, "c")]);
+        let (page, next) = select(fetched, "n", true, None, 2, 5).unwrap();
+        assert_eq!(ids(&page), vec!["d", "b"]);
+        assert_eq!(next.unwrap().id, "b");
+    }
+
+    #[test]
+    fn test_select_edge_after_cursor() {
+        // Only the trailing run is left after the cursor, so a larger window is needed.
+        let cursor = Cursor {
+            field: "n".into(),
+            value: json!(2),
+            id: "b".into(),
+        };
+        let fetched = records(&[(2, "a"), (2, "b"), (3, "c")]);
+        assert!(select(fetched, "n", false, Some(&cursor), 2, 3).is_none());
+
+        let fetched = records(&[(2, "a"), (2, "b"), (3, "c"), (3, "d"), (3, "e")]);
+        let (page, next) = select(fetched, "n", false, Some(&cursor), 2, 6).unwrap();
+        assert_eq!(ids(&page), vec!["c", "d"]);
+        assert_eq!(next.unwrap().id, "d");
+    }
+
+    #[test]
+    fn test_select_needs_more() {
+        let fetched = records(&[(1, "a"), (1, "b"), (1, "c")]);
+        assert!(select(fetched, "n", false, None, 2, 3).is_none());
+    }
+}
@@ -0,0 +1,84 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::client::sync::{index, MatchBy};
+use crate::client::Client;
+use crate::error::Result;
+use crate::patch::{json_patch_diff, PatchOperation};
+
+/// A record found in only one of the boxes compared by `diff()`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DiffRecord {
+    pub key: String,
+    pub id: String,
+    pub data: Value,
+}
+
+/// A record found in both boxes with different payloads.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ChangedRecord {
+    pub key: String,
+    pub id_a: String,
+    pub id_b: String,
+    /// JSON Patch operations transforming the payload in box A into the one in box B.
+    pub changes: Vec<PatchOperation>,
+}
+
+/// Differences between two boxes, from box A to box B. Serializable as JSON.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct BoxDiff {
+    /// Records only in box B.
+    pub added: Vec<DiffRecord>,
+    /// Records only in box A.
+    pub removed: Vec<DiffRecord>,
+    pub changed: Vec<ChangedRecord>,
+    /// Ids of records without the key field, which can't be compared.
+    pub unmatched: Vec<String>,
+}
+
+impl BoxDiff {
+    /// Whether both boxes hold the same payloads.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare all records of two boxes, matched by `key`. Meta keys aren't compared, and both boxes
+/// are read from the server, bypassing the cache.
+///
+/// Records are reported in order of their key. Fails with `Error::Duplicate` if a key isn't
+/// unique in a box.
+pub fn diff(a: &Client, b: &Client, key: &MatchBy) -> Result<BoxDiff> {
+    let mut report = BoxDiff::default();
+    let records_a = index(a.all_records()?, key, &mut report.unmatched)?;
+    let mut records_b = index(b.all_records()?, key, &mut report.unmatched)?;
+
+    for (key, (data_a, meta_a)) in records_a {
+        match records_b.remove(&key) {
+            Some((data_b, meta_b)) => {
+                if data_a != data_b {
+                    report.changed.push(ChangedRecord {
+                        changes: json_patch_diff(&data_a, &data_b)?,
+                        key,
+                        id_a: meta_a.id,
+                        id_b: meta_b.id,
+                    });
+                }
+            }
+            None => report.removed.push(DiffRecord {
+                key,
+                id: meta_a.id,
+                data: data_a,
+            }),
+        }
+    }
+    report.added = records_b
+        .into_iter()
+        .map(|(key, (data, meta))| DiffRecord {
+            key,
+            id: meta.id,
+            data,
+        })
+        .collect();
+    Ok(report)
+}
@@ -0,0 +1,92 @@
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+
+use crate::client::bulk::parallel;
+use crate::client::query_builder::MAX_LIMIT;
+use crate::client::Meta;
+use crate::error::Result;
+use crate::QueryBuilder;
+
+impl<'a> QueryBuilder<'a> {
+    /// Get all records matching the query from the configured `skip`, fetching pages of `limit`
+    /// records with at most `concurrency` requests in parallel.
+    ///
+    /// The number of pages is found first by probing single records with a binary search.
+    /// Pages are then fetched concurrently and put back in sort order. A record shifted to
+    /// another page by a concurrent write is returned once, by `_id`, and pages created after
+    /// probing are read one by one until a short page. The cache is bypassed.
+    pub fn fetch_all_parallel<T>(&self, concurrency: usize) -> Result<Vec<(T, Meta)>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        let limit = self.page_size().clamp(1, MAX_LIMIT);
+        let start = self.offset();
+        // Probes and pages must see the same state of the box, so neither is read from the cache.
+        let mut base = self.clone();
+        base.fresh = true;
+        let page = |i: u32| {
+            let mut query = base.clone();
+            query.skip(start + i * limit).limit(limit);
+            query.run::<T>()
+        };
+
+        let count = base.count_pages(limit)?;
+        let pages = parallel((0..count).collect(), concurrency, page);
+
+        let mut seen = HashSet::new();
+        let mut records = vec![];
+        let mut full = false;
+        for result in pages {
+            full = keep(result?, limit, &mut seen, &mut records);
+        }
+        let mut next = count;
+        while full {
+            full = keep(page(next)?, limit, &mut seen, &mut records);
+            next += 1;
+        }
+        Ok(records)
+    }
+
+    /// Count pages of `limit` records, by probing the first record of pages.
+    fn count_pages(&self, limit: u32) -> Result<u32> {
+        let exists = |i: u32| -> Result<bool> {
+            let mut query = self.clone();
+            query.skip(self.offset() + i * limit).limit(1);
+            Ok(!query.run_values()?.is_empty())
+        };
+        if !exists(0)? {
+            return Ok(0);
+        }
+
+        // Page `found` exists and page `missing` doesn't.
+        let (mut found, mut missing) = (0, 1);
+        while exists(missing)? {
+            found = missing;
+            missing *= 2;
+        }
+        while missing - found > 1 {
+            let mid = found + (missing - found) / 2;
+            if exists(mid)? {
+                found = mid;
+            } else {
+                missing = mid;
+            }
+        }
+        Ok(missing)
+    }
+}
+
+/// Append records of a page not seen yet, returning whether the page was full.
+fn keep<T>(
+    page: Vec<(T, Meta)>,
+    limit: u32,
+    seen: &mut HashSet<String>,
+    records: &mut Vec<(T, Meta)>,
+) -> bool {
+    let full = page.len() as u32 == limit;
+    records.extend(
+        page.into_iter()
+            .filter(|(_, meta)| seen.insert(meta.id.clone())),
+    );
+    full
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::client::Operation;
+use crate::error::Error;
+
+/// Upper bounds (in seconds) of latency histogram buckets.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Default)]
+struct Stats {
+    count: u64,
+    retries: u64,
+    errors: BTreeMap<&'static str, u64>,
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+}
+
+/// Metrics collected by `Client` for each operation.
+pub(crate) struct Metrics {
+    stats: Mutex<Vec<Stats>>,
+}
+
+impl Metrics {
+    pub(crate) fn new() -> Metrics {
+        let stats = Operation::ALL.iter().map(|_| Stats::default()).collect();
+        Metrics {
+            stats: Mutex::new(stats),
+        }
+    }
+
+    pub(crate) fn observe(&self, op: Operation, latency: Duration, error: Option<&Error>) {
+        let mut stats = self.stats.lock().unwrap();
+        let stats = &mut stats[op as usize];
+        stats.count += 1;
+        if let Some(err) = error {
+            *stats.errors.entry(error_class(err)).or_insert(0) += 1;
+        }
+        let secs = latency.as_secs_f64();
+        stats.sum += secs;
+        for (bucket, bound) in stats.buckets.iter_mut().zip(BUCKETS.iter()) {
+            if secs <= *bound {
+                *bucket += 1;
+            }
+        }
+    }
+
+    pub(crate) fn retry(&self, op: Operation) {
+        self.stats.lock().unwrap()[op as usize].retries += 1;
+    }
+
+    pub(crate) fn snapshot(&self) -> MetricsSnapshot {
+        let stats = self.stats.lock().unwrap();
+        let operations = Operation::ALL
+            .iter()
+            .zip(stats.iter())
+            .map(|(op, stats)| OperationMetrics {
+                operation: op.as_str(),
+                count: stats.count,
+                retries: stats.retries,
+                errors: stats.errors.clone(),
+                latency: Histogram {
+                    buckets: BUCKETS
+                        .iter()
+                        .cloned()
+                        .zip(stats.buckets.iter().cloned())
+                        .collect(),
+                    sum: stats.sum,
+                    count: stats.count,
+                },
+            })
+            .collect();
+        MetricsSnapshot { operations }
+    }
+}
+
+fn error_class(err: &Error) -> &'static str {
+    match err {
+        Error::Network { .. } => "network",
+        Error::Json { .. } => "json",
+        Error::General { code, .. } if *code >= 500 => "server",
+        Error::General { .. } => "client",
+        Error::Io { .. } => "io",
+        Error::Cassette { .. } => "cassette",
+        Error::PatchTest { .. } | Error::InvalidPatch { .. } => "patch",
+        Error::Conflict { .. } => "conflict",
+        Error::Duplicate { .. } => "duplicate",
+        Error::Interrupted { source, .. } => error_class(source),
+        Error::CheckpointNotFound { .. } => "checkpoint",
+        Error::UnsupportedVersion { .. } => "schema",
+        Error::Validation { .. } => "validation",
+        Error::PayloadTooLarge { .. } | Error::ReservedKey { .. } => "payload",
+        Error::CountMismatch { .. } => "count",
+        Error::InvalidCursor { .. } => "cursor",
+    }
+}
+
+/// A point-in-time copy of metrics collected by `Client`. Use `Client::metrics()` to get it.
+#[derive(Clone, Debug)]
+pub struct MetricsSnapshot {
+    pub operations: Vec<OperationMetrics>,
+}
+
+/// Metrics of a single kind of operation, such as `create` or `read_by_query`.
+#[derive(Clone, Debug)]
+pub struct OperationMetrics {
+    pub operation: &'static str,
+    pub count: u64,
+    pub retries: u64,
+    /// Number of failures by error class: `network`, `json`, `client` (4xx), `server` (5xx), ...
+    pub errors: BTreeMap<&'static str, u64>,
+    pub latency: Histogram,
+}
+
+/// Latency histogram in seconds. Buckets are cumulative pairs of upper bound and count.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    pub buckets: Vec<(f64, u64)>,
+    pub sum: f64,
+    pub count: u64,
+}
+
+impl MetricsSnapshot {
+    /// Get metrics of an operation by name.
+    pub fn operation(&self, name: &str) -> Option<&OperationMetrics> {
+        self.operations.iter().find(|m| m.operation == name)
+    }
+
+    /// Render metrics in Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP jsonbox_operations_total Number of operations issued.\n");
+        out.push_str("# TYPE jsonbox_operations_total counter\n");
+        for m in &self.operations {
+            let _ = writeln!(
+                out,
+                "jsonbox_operations_total{{operation=\"{}\"}} {}",
+                m.operation, m.count
+            );
+        }
+
+        out.push_str("# HELP jsonbox_errors_total Number of failed operations by error class.\n");
+        out.push_str("# TYPE jsonbox_errors_total counter\n");
+        for m in &self.operations {
+            for (class, count) in &m.errors {
+                let _ = writeln!(
+                    out,
+                    "jsonbox_errors_total{{operation=\"{}\",class=\"{}\"}} {}",
+                    m.operation, class, count
+                );
+            }
+        }
+
+        out.push_str("# HELP jsonbox_retries_total Number of retried operations.\n");
+        out.push_str("# TYPE jsonbox_retries_total counter\n");
+        for m in &self.operations {
+            let _ = writeln!(
+                out,
+                "jsonbox_retries_total{{operation=\"{}\"}} {}",
+                m.operation, m.retries
+            );
+        }
+
+        out.push_str(
+            "# HELP jsonbox_operation_duration_seconds Latency of operations in seconds.\n",
+        );
+        out.push_str("# TYPE jsonbox_operation_duration_seconds histogram\n");
+        for m in &self.operations {
+            for (bound, count) in &m.latency.buckets {
+                let _ = writeln!(
+                    out,
+                    "jsonbox_operation_duration_seconds_bucket{{operation=\"{}\",le=\"{}\"}} {}",
+                    m.operation, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "jsonbox_operation_duration_seconds_bucket{{operation=\"{}\",le=\"+Inf\"}} {}",
+                m.operation, m.latency.count
+            );
+            let _ = writeln!(
+                out,
+                "jsonbox_operation_duration_seconds_sum{{operation=\"{}\"}} {}",
+                m.operation, m.latency.sum
+            );
+            let _ = writeln!(
+                out,
+                "jsonbox_operation_duration_seconds_count{{operation=\"{}\"}} {}",
+                m.operation, m.latency.count
+            );
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observe() {
+        let metrics = Metrics::new();
+        metrics.observe(Operation::Create, Duration::from_millis(20), None);
+        metrics.observe(
+            Operation::Create,
+            Duration::from_millis(200),
+            Some(&Error::General {
+                code: 400,
+                message: "Invalid".into(),
+            }),
+        );
+
+        let snapshot = metrics.snapshot();
+        let create = snapshot.operation("create").unwrap();
+        assert_eq!(create.count, 2);
+        assert_eq!(create.errors.get("client"), Some(&1));
+        assert_eq!(create.latency.count, 2);
+        assert_eq!(create.latency.buckets[2], (0.025, 1));
+        assert_eq!(create.latency.buckets[5], (0.25, 2));
+        assert_eq!(snapshot.operation("delete").unwrap().count, 0);
+    }
+
+    #[test]
+    fn test_to_prometheus() {
+        let metrics = Metrics::new();
+        metrics.observe(Operation::Delete, Duration::from_millis(20), None);
+
+        let text = metrics.snapshot().to_prometheus();
+        assert!(text.contains("# TYPE jsonbox_operations_total counter\n"));
+        assert!(text.contains("jsonbox_operations_total{operation=\"delete\"} 1\n"));
+        assert!(text.contains("jsonbox_operations_total{operation=\"create\"} 0\n"));
+        assert!(text.contains(
+            "jsonbox_operation_duration_seconds_bucket{operation=\"delete\",le=\"0.025\"} 1\n"
+        ));
+        assert!(text.contains(
+            "jsonbox_operation_duration_seconds_bucket{operation=\"delete\",le=\"+Inf\"} 1\n"
+        ));
+        assert!(text.contains("jsonbox_operation_duration_seconds_count{operation=\"delete\"} 1\n"));
+    }
+}
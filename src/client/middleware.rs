@@ -0,0 +1,131 @@
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+
+use crate::error::Result;
+
+/// An outgoing request, as seen by `Middleware`.
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Option<String>,
+}
+
+/// An incoming response, as seen by `Middleware`.
+///
+/// Error responses are passed through the chain too. They're turned into `Error::General`
+/// after the outermost middleware returns.
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+/// A layer wrapping every request issued by `Client`.
+///
+/// Call `next.run(req)` to pass the (possibly modified) request to the next layer and finally
+/// to the server, or return a `Response` without calling it to short-circuit the request.
+/// Closures with the same signature implement this trait.
+///
+/// ```ignore
+/// let client = Client::new("<BOX_ID>").with_middleware(|mut req: Request, next: Next| {
+///     req.headers.insert("x-correlation-id", HeaderValue::from_static("42"));
+///     next.run(req)
+/// });
+/// ```
+pub trait Middleware: Send + Sync {
+    fn handle(&self, req: Request, next: Next) -> Result<Response>;
+}
+
+impl<F> Middleware for F
+where
+    F: Fn(Request, Next) -> Result<Response> + Send + Sync,
+{
+    fn handle(&self, req: Request, next: Next) -> Result<Response> {
+        self(req, next)
+    }
+}
+
+/// The rest of the middleware chain.
+pub struct Next<'c> {
+    middlewares: &'c [Box<dyn Middleware>],
+    transport: &'c dyn Fn(Request) -> Result<Response>,
+}
+
+impl<'c> Next<'c> {
+    pub(in crate::client) fn new(
+        middlewares: &'c [Box<dyn Middleware>],
+        transport: &'c dyn Fn(Request) -> Result<Response>,
+    ) -> Next<'c> {
+        Next {
+            middlewares,
+            transport,
+        }
+    }
+
+    /// Pass the request to the next layer.
+    pub fn run(self, req: Request) -> Result<Response> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => middleware.handle(req, Next::new(rest, self.transport)),
+            None => (self.transport)(req),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn echo(req: Request) -> Result<Response> {
+        Ok(Response {
+            status: 200,
+            headers: req.headers,
+            body: req.body.unwrap_or_default(),
+        })
+    }
+
+    fn request() -> Request {
+        Request {
+            method: Method::GET,
+            url: "https://jsonbox.io/xxx".into(),
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    #[test]
+    fn test_run_in_order() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let (first, second) = (log.clone(), log.clone());
+        let middlewares: Vec<Box<dyn Middleware>> = vec![
+            Box::new(move |req: Request, next: Next| {
+                first.lock().unwrap().push("first");
+                next.run(req)
+            }),
+            Box::new(move |req: Request, next: Next| {
+                second.lock().unwrap().push("second");
+                next.run(req)
+            }),
+        ];
+        let res = Next::new(&middlewares, &echo).run(request());
+        assert!(res.is_ok());
+        assert_eq!(*log.lock().unwrap(), vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_short_circuit() {
+        let middlewares: Vec<Box<dyn Middleware>> = vec![Box::new(|_: Request, _: Next| {
+            Ok(Response {
+                status: 204,
+                headers: HeaderMap::new(),
+                body: String::new(),
+            })
+        })];
+        let transport = |_: Request| -> Result<Response> { panic!("must not be called") };
+        let res = Next::new(&middlewares, &transport).run(request()).unwrap();
+        assert_eq!(res.status, 204);
+    }
+}
@@ -0,0 +1,197 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{from_value, to_value, Value};
+use snafu::ResultExt;
+
+use crate::client::Meta;
+use crate::error::{self, Error, Result};
+use crate::patch::{json_patch_diff, PatchOperation};
+use crate::QueryBuilder;
+
+/// Options of `QueryBuilder::for_each_update_with`.
+#[derive(Clone, Debug, Default)]
+pub struct MigrationOptions {
+    dry_run: bool,
+    resume_from: Option<String>,
+}
+
+impl MigrationOptions {
+    pub fn new() -> MigrationOptions {
+        MigrationOptions::default()
+    }
+
+    /// Don't write anything, report the changes as JSON Patch operations instead.
+    pub fn dry_run(mut self) -> MigrationOptions {
+        self.dry_run = true;
+        self
+    }
+
+    /// Skip records up to and including the one with this id, which is usually the checkpoint
+    /// of an interrupted run. If no record has this id, e.g. it was deleted since,
+    /// `Error::CheckpointNotFound` is returned after scanning without processing anything.
+    pub fn resume_from(mut self, id: &str) -> MigrationOptions {
+        self.resume_from = Some(id.to_string());
+        self
+    }
+}
+
+/// Report of `QueryBuilder::for_each_update`.
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// Number of records passed to the closure.
+    pub scanned: usize,
+    /// Number of records changed by the closure, which were written unless in dry-run mode.
+    pub changed: usize,
+    /// Changes of each changed record. Only collected in dry-run mode.
+    pub diffs: Vec<(String, Vec<PatchOperation>)>,
+    /// Id of the last processed record.
+    pub checkpoint: Option<String>,
+}
+
+impl<'a> QueryBuilder<'a> {
+    /// Rewrite every record matching the query with `f`.
+    ///
+    /// Records are fetched page by page (as configured by `limit`) without meta keys and only
+    /// those changed by `f` are written back. If the run fails midway, `Error::Interrupted`
+    /// carries the id of the last processed record, to be passed to
+    /// `MigrationOptions::resume_from`.
+    ///
+    /// Pages are fetched with `skip`, so changing fields used by the filter or the sort order
+    /// may cause records to be missed. Keep the default order (`_createdOn`) and make `f`
+    /// idempotent, so running it again until nothing changes is safe.
+    pub fn for_each_update<F>(&self, f: F) -> Result<MigrationReport>
+    where
+        F: FnMut(&mut Value),
+    {
+        self.for_each_update_with(&MigrationOptions::default(), f)
+    }
+
+    /// Same as `for_each_update` with options, e.g. dry-run mode.
+    pub fn for_each_update_with<F>(
+        &self,
+        options: &MigrationOptions,
+        mut f: F,
+    ) -> Result<MigrationReport>
+    where
+        F: FnMut(&mut Value),
+    {
+        self.migrate(options, |record| {
+            let mut updated = record.clone();
+            f(&mut updated);
+            Ok(updated)
+        })
+    }
+
+    /// Typed variant of `for_each_update`, converting each record from `T` to `U`.
+    ///
+    /// A record is written back if `U` serializes differently than the stored record.
+    pub fn for_each_update_as<T, U, F>(
+        &self,
+        options: &MigrationOptions,
+        mut f: F,
+    ) -> Result<MigrationReport>
+    where
+        T: DeserializeOwned,
+        U: Serialize,
+        F: FnMut(T) -> U,
+    {
+        self.migrate(options, |record| {
+            let data: T = from_value(record.clone()).context(error::Json { reason: "data" })?;
+            to_value(f(data)).context(error::Json { reason: "payload" })
+        })
+    }
+
+    pub(in crate::client) fn migrate<F>(
+        &self,
+        options: &MigrationOptions,
+        mut f: F,
+    ) -> Result<MigrationReport>
+    where
+        F: FnMut(&Value) -> Result<Value>,
+    {
+        let mut report = MigrationReport {
+            checkpoint: options.resume_from.clone(),
+            ..MigrationReport::default()
+        };
+        let mut resume = Resume::new(options.resume_from.as_deref());
+        for page in self.pages() {
+            let page = page.map_err(|err| interrupted(&report, err))?;
+            for (record, meta) in page {
+                if resume.skip(&meta.id) {
+                    continue;
+                }
+                self.migrate_record(options, &mut f, &mut report, record, meta)
+                    .map_err(|err| interrupted(&report, err))?;
+            }
+        }
+        resume.finish()?;
+        Ok(report)
+    }
+
+    fn migrate_record<F>(
+        &self,
+        options: &MigrationOptions,
+        f: &mut F,
+        report: &mut MigrationReport,
+        record: Value,
+        meta: Meta,
+    ) -> Result<()>
+    where
+        F: FnMut(&Value) -> Result<Value>,
+    {
+        let updated = f(&record)?;
+        report.scanned += 1;
+        if updated != record {
+            report.changed += 1;
+            if options.dry_run {
+                report
+                    .diffs
+                    .push((meta.id.clone(), json_patch_diff(&record, &updated)?));
+            } else {
+                self.client.update_value(&meta.id, &updated)?;
+            }
+        }
+        report.checkpoint = Some(meta.id);
+        Ok(())
+    }
+}
+
+/// Skips records up to and including a checkpoint, to resume an interrupted run.
+pub(in crate::client) struct Resume {
+    checkpoint: Option<String>,
+    found: bool,
+}
+
+impl Resume {
+    pub(in crate::client) fn new(checkpoint: Option<&str>) -> Resume {
+        Resume {
+            checkpoint: checkpoint.map(str::to_string),
+            found: false,
+        }
+    }
+
+    /// Whether the record `id` was processed by the interrupted run, given in scan order.
+    pub(in crate::client) fn skip(&mut self, id: &str) -> bool {
+        match &self.checkpoint {
+            Some(checkpoint) if !self.found => {
+                self.found = checkpoint == id;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Fail with `Error::CheckpointNotFound` if the scan never reached the checkpoint.
+    pub(in crate::client) fn finish(self) -> Result<()> {
+        match self.checkpoint {
+            Some(checkpoint) if !self.found => Err(Error::CheckpointNotFound { checkpoint }),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn interrupted(report: &MigrationReport, err: Error) -> Error {
+    Error::Interrupted {
+        checkpoint: report.checkpoint.clone(),
+        source: Box::new(err),
+    }
+}
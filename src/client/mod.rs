@@ -1,14 +1,47 @@
+pub mod bulk;
+pub mod cache;
+pub mod cassette;
+pub mod copy;
+pub mod cursor;
+pub mod diff;
+pub mod fetch;
+pub mod metrics;
+pub mod middleware;
+pub mod migrate;
+pub mod offline;
+pub mod optimistic;
+pub mod patch;
 pub mod query_builder;
+pub mod raw;
+pub mod rules;
+pub mod schema;
+pub mod stream;
+pub mod sync;
+pub mod upsert;
+pub mod validate;
+pub mod value;
+pub mod watch;
 
-use reqwest;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use reqwest::Method;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use serde_json::from_str;
+use serde_json::{from_str, from_value, Value};
 use snafu::ResultExt;
 use std::convert::From;
+use std::time::Instant;
 
+use crate::client::cache::{Cache, Key};
+use crate::client::metrics::Metrics;
+use crate::client::offline::Journal;
+use crate::client::schema::Schema;
+use crate::client::validate::Validator;
 use crate::error::{self, Error, Result};
+use crate::trace;
 use crate::url;
-use crate::QueryBuilder;
+use crate::{MetricsSnapshot, Middleware, Next, QueryBuilder, Request, Response};
+
+/// Maximum size of a request body accepted by jsonbox, in bytes.
+pub(crate) const SIZE_LIMIT: usize = 10 * 1024;
 
 #[derive(Deserialize, Debug)]
 struct MetaRaw {
@@ -46,9 +79,59 @@ pub struct ErrorMessage {
     pub message: String,
 }
 
+/// Kind of operation issued by `Client`, used to label traces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Operation {
+    Create,
+    CreateBulk,
+    ReadById,
+    ReadByQuery,
+    Update,
+    Delete,
+}
+
+impl Operation {
+    pub(crate) const ALL: [Operation; 6] = [
+        Operation::Create,
+        Operation::CreateBulk,
+        Operation::ReadById,
+        Operation::ReadByQuery,
+        Operation::Update,
+        Operation::Delete,
+    ];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Operation::Create => "create",
+            Operation::CreateBulk => "create_bulk",
+            Operation::ReadById => "read_by_id",
+            Operation::ReadByQuery => "read_by_query",
+            Operation::Update => "update",
+            Operation::Delete => "delete",
+        }
+    }
+
+    pub(crate) fn method(self) -> Method {
+        match self {
+            Operation::Create | Operation::CreateBulk => Method::POST,
+            Operation::ReadById | Operation::ReadByQuery => Method::GET,
+            Operation::Update => Method::PUT,
+            Operation::Delete => Method::DELETE,
+        }
+    }
+}
+
 pub struct Client<'a> {
     base_url: &'a str,
     box_id: &'a str,
+    middlewares: Vec<Box<dyn Middleware>>,
+    metrics: Metrics,
+    conflict_retries: u32,
+    schemas: Vec<Schema>,
+    validators: Vec<Box<dyn Validator>>,
+    escape_keys: bool,
+    cache: Option<Cache>,
+    journal: Option<Journal>,
 }
 
 impl<'a> Client<'a> {
@@ -56,6 +139,14 @@ impl<'a> Client<'a> {
         Client {
             base_url: url::BASE_URL,
             box_id,
+            middlewares: vec![],
+            metrics: Metrics::new(),
+            conflict_retries: optimistic::CONFLICT_RETRIES,
+            schemas: vec![],
+            validators: vec![],
+            escape_keys: false,
+            cache: None,
+            journal: None,
         }
     }
 
@@ -64,58 +155,61 @@ impl<'a> Client<'a> {
         self
     }
 
+    /// Append a middleware to the chain. Middlewares run in the order they're added.
+    pub fn with_middleware<M>(mut self, middleware: M) -> Client<'a>
+    where
+        M: Middleware + 'static,
+    {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
     pub fn create<T>(&self, data: &T) -> Result<(T, Meta)>
     where
         T: Serialize + DeserializeOwned,
     {
-        let client = reqwest::Client::new();
-        let mut res = client
-            .post(&url::of_box(&self.base_url, &self.box_id))
-            .json(&data)
-            .send()
-            .context(error::Network {})?;
-        if res.status().is_success() {
-            let raw = res.text().context(error::Network {})?;
-            let data: T = from_str(&raw).context(error::Json { reason: "data" })?;
-            let meta: MetaRaw = from_str(&raw).context(error::Json { reason: "meta" })?;
-            Ok((data, Meta::from(meta)))
-        } else {
-            let err: ErrorMessage = res.json().context(error::Network {})?;
-            Err(Error::General {
-                code: res.status().as_u16(),
-                message: err.message,
-            })
+        match &self.journal {
+            Some(journal) => self.create_offline(journal, data),
+            None => self.create_online(data),
         }
     }
 
-    pub fn create_bulk<T>(&self, data: &Vec<T>) -> Result<Vec<(T, Meta)>>
+    fn create_online<T>(&self, data: &T) -> Result<(T, Meta)>
     where
         T: Serialize + DeserializeOwned,
     {
-        let client = reqwest::Client::new();
-        let mut res = client
-            .post(&url::of_box(&self.base_url, &self.box_id))
-            .json(&data)
-            .send()
-            .context(error::Network {})?;
-        if res.status().is_success() {
-            let raw = res.text().context(error::Network {})?;
-            let data: Vec<T> = from_str(&raw).context(error::Json { reason: "data" })?;
-            let meta: Vec<MetaRaw> = from_str(&raw).context(error::Json { reason: "meta" })?;
-            Ok(data
-                .into_iter()
-                .zip(meta.into_iter().map(|meta| Meta::from(meta)))
-                .collect())
-        } else {
-            let err: ErrorMessage = res.json().context(error::Network {})?;
-            Err(Error::General {
-                code: res.status().as_u16(),
-                message: err.message,
-            })
-        }
+        self.observe(Operation::Create, None, None, || {
+            let url = url::of_box(self.base_url, self.box_id);
+            let body = self.payload(data)?;
+            let res = self.send(Operation::Create, &url, Some(body));
+            self.cache_invalidate(None);
+            self.decode_record(&res?)
+        })
+    }
+
+    pub fn create_bulk<T>(&self, data: &[T]) -> Result<Vec<(T, Meta)>>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let body = format!("[{}]", self.payloads(data)?.join(","));
+        self.create_bulk_raw(body)
     }
 
-    pub fn read(&self) -> QueryBuilder {
+    /// Send a bulk `CREATE` with an already serialized JSON array.
+    fn create_bulk_raw<T>(&self, body: String) -> Result<Vec<(T, Meta)>>
+    where
+        T: DeserializeOwned,
+    {
+        self.observe(Operation::CreateBulk, None, None, || {
+            rules::check_size(&body)?;
+            let url = url::of_box(self.base_url, self.box_id);
+            let res = self.send(Operation::CreateBulk, &url, Some(body));
+            self.cache_invalidate(None);
+            self.decode_records(&res?)
+        })
+    }
+
+    pub fn read(&self) -> QueryBuilder<'_> {
         QueryBuilder::new(self)
     }
 
@@ -123,87 +217,248 @@ impl<'a> Client<'a> {
     where
         T: DeserializeOwned,
     {
-        let url = url::of_record(&self.base_url, &self.box_id, id);
-        let mut res = reqwest::get(&url).context(error::Network {})?;
-        if res.status().is_success() {
-            let raw = res.text().context(error::Network {})?;
-            let data: T = from_str(&raw).context(error::Json { reason: "data" })?;
-            let meta: MetaRaw = from_str(&raw).context(error::Json { reason: "meta" })?;
-            Ok((data, Meta::from(meta)))
-        } else {
-            let err: ErrorMessage = res.json().context(error::Network {})?;
-            Err(Error::General {
-                code: res.status().as_u16(),
-                message: err.message,
-            })
+        self.read_by_id_with(id, |raw| self.decode_record(raw))
+    }
+
+    /// Read a record by id and decode the raw response body with `decode`, going through the cache.
+    fn read_by_id_with<R, F>(&self, id: &str, decode: F) -> Result<R>
+    where
+        F: Fn(&str) -> Result<R>,
+    {
+        let key = Key::Id(id.to_string());
+        if let Some(raw) = self.cache_get(&key) {
+            return decode(&raw);
         }
+        let generation = self.cache_generation();
+        self.observe(Operation::ReadById, Some(id), None, || {
+            let url = url::of_record(self.base_url, self.box_id, id);
+            let raw = self.send(Operation::ReadById, &url, None)?;
+            let record = decode(&raw)?;
+            self.cache_put(key, &raw, generation);
+            Ok(record)
+        })
+    }
+
+    /// Read a record by id from the server, bypassing the cache.
+    fn fetch_by_id<T>(&self, id: &str) -> Result<(T, Meta)>
+    where
+        T: DeserializeOwned,
+    {
+        self.observe(Operation::ReadById, Some(id), None, || {
+            let url = url::of_record(self.base_url, self.box_id, id);
+            let raw = self.send(Operation::ReadById, &url, None)?;
+            self.decode_record(&raw)
+        })
     }
 
     fn read_by_query<T>(&self, query: &QueryBuilder) -> Result<Vec<(T, Meta)>>
     where
         T: DeserializeOwned,
     {
-        let url = &url::of_query(&self.base_url, &self.box_id, &query.to_string());
-        let mut res = reqwest::get(url).context(error::Network {})?;
-        if res.status().is_success() {
-            let raw = res.text().context(error::Network {})?;
-            let data: Vec<T> = from_str(&raw).context(error::Json { reason: "data" })?;
-            let meta: Vec<MetaRaw> = from_str(&raw).context(error::Json { reason: "meta" })?;
-            Ok(data
-                .into_iter()
-                .zip(meta.into_iter().map(|meta| Meta::from(meta)))
-                .collect())
-        } else {
-            let err: ErrorMessage = res.json().context(error::Network {})?;
-            Err(Error::General {
-                code: res.status().as_u16(),
-                message: err.message,
-            })
+        self.read_by_query_with(query, |raw| self.decode_records(raw))
+    }
+
+    /// Run a query and decode the raw response body with `decode`, going through the cache.
+    fn read_by_query_with<R, F>(&self, query: &QueryBuilder, decode: F) -> Result<R>
+    where
+        F: Fn(&str) -> Result<R>,
+    {
+        let fresh = query.fresh;
+        let query = query.to_string();
+        let key = Key::Query(query.clone());
+        if !fresh {
+            if let Some(raw) = self.cache_get(&key) {
+                return decode(&raw);
+            }
         }
+        let generation = self.cache_generation();
+        self.observe(Operation::ReadByQuery, None, Some(&query), || {
+            let url = url::of_query(self.base_url, self.box_id, &query);
+            let raw = self.send(Operation::ReadByQuery, &url, None)?;
+            let records = decode(&raw)?;
+            self.cache_put(key, &raw, generation);
+            Ok(records)
+        })
     }
 
     pub fn update<T>(&self, id: &str, data: &T) -> Result<()>
     where
         T: Serialize,
     {
-        let client = reqwest::Client::new();
-        let mut res = client
-            .put(&url::of_record(&self.base_url, &self.box_id, id))
-            .json(&data)
-            .send()
-            .context(error::Network {})?;
-        if res.status().is_success() {
-            Ok(())
-        } else {
-            let err: ErrorMessage = res.json().context(error::Network {})?;
-            Err(error::Error::General {
-                code: res.status().as_u16(),
-                message: err.message,
-            })
+        match &self.journal {
+            Some(journal) => self.update_offline(journal, id, data),
+            None => self.update_online(id, data),
         }
     }
 
+    fn update_online<T>(&self, id: &str, data: &T) -> Result<()>
+    where
+        T: Serialize,
+    {
+        self.observe(Operation::Update, Some(id), None, || {
+            let url = url::of_record(self.base_url, self.box_id, id);
+            let body = self.payload(data)?;
+            let res = self.send(Operation::Update, &url, Some(body));
+            self.cache_invalidate(Some(id));
+            res.map(|_| ())
+        })
+    }
+
     pub fn delete(&self, id: &str) -> Result<()> {
-        let client = reqwest::Client::new();
-        let mut res = client
-            .delete(&url::of_record(&self.base_url, &self.box_id, id))
-            .send()
-            .context(error::Network {})?;
-        if res.status().is_success() {
-            Ok(())
+        match &self.journal {
+            Some(journal) => self.delete_offline(journal, id),
+            None => self.delete_online(id),
+        }
+    }
+
+    fn delete_online(&self, id: &str) -> Result<()> {
+        self.observe(Operation::Delete, Some(id), None, || {
+            let url = url::of_record(self.base_url, self.box_id, id);
+            let res = self.send(Operation::Delete, &url, None);
+            self.cache_invalidate(Some(id));
+            res.map(|_| ())
+        })
+    }
+
+    /// Get a snapshot of metrics collected so far.
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Run an operation in its trace span and record its metrics.
+    fn observe<R, F>(&self, op: Operation, id: Option<&str>, query: Option<&str>, f: F) -> Result<R>
+    where
+        F: FnOnce() -> Result<R>,
+    {
+        let _span = trace::span(op, self.box_id, id, query);
+        let started = Instant::now();
+        let result = f();
+        self.metrics
+            .observe(op, started.elapsed(), result.as_ref().err());
+        result
+    }
+
+    /// Send a request through the middleware chain and return the raw body of a successful response.
+    fn send(&self, op: Operation, url: &str, body: Option<String>) -> Result<String> {
+        let mut headers = HeaderMap::new();
+        if body.is_some() {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        }
+        let req = Request {
+            method: op.method(),
+            url: url.to_string(),
+            headers,
+            body,
+        };
+
+        let started = Instant::now();
+        let res = match Next::new(&self.middlewares, &transport).run(req) {
+            Ok(res) => res,
+            Err(err) => {
+                trace::failed(&err, started.elapsed());
+                return Err(err);
+            }
+        };
+        trace::completed(res.status, res.body.len(), started.elapsed());
+
+        if (200..300).contains(&res.status) {
+            Ok(res.body)
         } else {
-            let err: ErrorMessage = res.json().context(error::Network {})?;
-            Err(Error::General {
-                code: res.status().as_u16(),
-                message: err.message,
-            })
+            Err(status_error(res.status, res.body))
         }
     }
 }
 
+/// Turn an error response into `Error::General`.
+fn status_error(code: u16, body: String) -> Error {
+    match from_str::<ErrorMessage>(&body) {
+        Ok(err) => Error::General {
+            code,
+            message: err.message,
+        },
+        Err(source) => Error::Json {
+            reason: "error".to_string(),
+            source,
+        },
+    }
+}
+
+impl<'a> Client<'a> {
+    fn decode_record<T>(&self, raw: &str) -> Result<(T, Meta)>
+    where
+        T: DeserializeOwned,
+    {
+        let data: T = if self.escape_keys || self.schema_of::<T>().is_some() {
+            let record: Value = from_str(raw).context(error::Json { reason: "data" })?;
+            from_value(self.restore::<T>(record)?).context(error::Json { reason: "data" })?
+        } else {
+            from_str(raw).context(error::Json { reason: "data" })?
+        };
+        let meta: MetaRaw = from_str(raw).context(error::Json { reason: "meta" })?;
+        Ok((data, Meta::from(meta)))
+    }
+
+    fn decode_records<T>(&self, raw: &str) -> Result<Vec<(T, Meta)>>
+    where
+        T: DeserializeOwned,
+    {
+        let data: Vec<T> = if self.escape_keys || self.schema_of::<T>().is_some() {
+            let records: Vec<Value> = from_str(raw).context(error::Json { reason: "data" })?;
+            records
+                .into_iter()
+                .map(|record| {
+                    from_value(self.restore::<T>(record)?).context(error::Json { reason: "data" })
+                })
+                .collect::<Result<_>>()?
+        } else {
+            from_str(raw).context(error::Json { reason: "data" })?
+        };
+        let meta: Vec<MetaRaw> = from_str(raw).context(error::Json { reason: "meta" })?;
+        Ok(data
+            .into_iter()
+            .zip(meta.into_iter().map(Meta::from))
+            .collect())
+    }
+
+    /// Unescape keys and upgrade the schema of a record read from the server, as configured.
+    fn restore<T>(&self, mut record: Value) -> Result<Value> {
+        if self.escape_keys {
+            record = rules::unescape_keys(record);
+        }
+        match self.schema_of::<T>() {
+            Some(schema) => schema.upgrade(record),
+            None => Ok(record),
+        }
+    }
+}
+
+/// The innermost layer of the middleware chain, which actually talks to the server.
+fn transport(req: Request) -> Result<Response> {
+    let client = reqwest::Client::new();
+    let mut builder = client.request(req.method, &req.url).headers(req.headers);
+    if let Some(body) = req.body {
+        builder = builder.body(body);
+    }
+    let mut res = builder.send().context(error::Network {})?;
+    Ok(Response {
+        status: res.status().as_u16(),
+        headers: res.headers().clone(),
+        body: res.text().context(error::Network {})?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use matches::*;
+
+    #[test]
+    fn test_status_error() {
+        let err = status_error(400, r#"{"message":"Invalid Box ID"}"#.to_string());
+        assert_matches!(err, Error::General { code: 400, ref message } if message == "Invalid Box ID");
+        let err = status_error(502, "<html>Bad Gateway</html>".to_string());
+        assert_matches!(err, Error::Json { ref reason, .. } if reason == "error");
+    }
 
     #[test]
     fn test_new() {
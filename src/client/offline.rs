@@ -0,0 +1,358 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{from_str, from_value, to_string, to_value, Value};
+use snafu::ResultExt;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::client::{Client, Meta};
+use crate::error::{self, Error, Result};
+
+/// A write waiting in the journal. Payloads are kept as given, checks run again on replay.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Entry {
+    Create { local_id: String, data: Value },
+    Update { id: String, data: Value },
+    Delete { id: String },
+}
+
+impl Entry {
+    fn id(&self) -> &str {
+        match self {
+            Entry::Create { local_id, .. } => local_id,
+            Entry::Update { id, .. } | Entry::Delete { id } => id,
+        }
+    }
+
+    /// Replace a temporary local id by the real one.
+    fn resolve(&mut self, local_id: &str, real_id: &str) {
+        match self {
+            Entry::Update { id, .. } | Entry::Delete { id } if id == local_id => {
+                *id = real_id.to_string()
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A journal of writes as a file of JSON lines, one `Entry` per line.
+pub(crate) struct Journal {
+    path: PathBuf,
+    /// Held while reading or changing the file, and during `flush()` so writes issued meanwhile
+    /// are journaled after the replayed ones. Not held during online writes.
+    lock: Mutex<()>,
+    seq: AtomicU64,
+}
+
+impl Journal {
+    fn new(path: &Path) -> Journal {
+        Journal {
+            path: path.to_path_buf(),
+            lock: Mutex::new(()),
+            seq: AtomicU64::new(0),
+        }
+    }
+
+    /// Lock the journal. It guards no data, so a panic while it was held doesn't matter.
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn is_empty(&self) -> Result<bool> {
+        match fs::metadata(&self.path) {
+            Ok(meta) => Ok(meta.len() == 0),
+            Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(true),
+            Err(err) => Err(err).context(error::Io {}),
+        }
+    }
+
+    fn load(&self) -> Result<VecDeque<Entry>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(VecDeque::new()),
+            Err(err) => return Err(err).context(error::Io {}),
+        };
+        let mut entries = VecDeque::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.context(error::Io {})?;
+            if !line.trim().is_empty() {
+                entries.push_back(from_str(&line).context(error::Json { reason: "journal" })?);
+            }
+        }
+        Ok(entries)
+    }
+
+    fn append(&self, entry: &Entry) -> Result<()> {
+        let line = to_string(entry).context(error::Json { reason: "journal" })?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context(error::Io {})?;
+        writeln!(file, "{}", line).context(error::Io {})?;
+        file.sync_all().context(error::Io {})
+    }
+
+    /// Replace the journal with `entries`, atomically.
+    fn store(&self, entries: &VecDeque<Entry>) -> Result<()> {
+        if entries.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(()),
+                result => result.context(error::Io {}),
+            };
+        }
+        let tmp = self.path.with_extension("tmp");
+        let mut file = File::create(&tmp).context(error::Io {})?;
+        for entry in entries {
+            let line = to_string(entry).context(error::Json { reason: "journal" })?;
+            writeln!(file, "{}", line).context(error::Io {})?;
+        }
+        file.sync_all().context(error::Io {})?;
+        fs::rename(&tmp, &self.path).context(error::Io {})
+    }
+
+    fn local_id(&self) -> String {
+        let seq = self.seq.fetch_add(1, Ordering::SeqCst);
+        format!("local-{}-{}", millis_since_epoch(), seq)
+    }
+}
+
+/// Result of `Client::flush()`.
+#[derive(Debug, Default)]
+pub struct FlushReport {
+    /// Number of writes accepted by the server.
+    pub replayed: usize,
+    /// Real `_id` of each record created, by the temporary local id returned by `create`.
+    pub ids: BTreeMap<String, String>,
+    /// Writes rejected by the server with the error, by record id. They're dropped from the journal.
+    pub rejected: Vec<(String, Error)>,
+    /// Number of writes left in the journal because the network became unavailable again.
+    pub pending: usize,
+}
+
+impl<'a> Client<'a> {
+    /// Queue writes in a journal at `path` while the network is unavailable.
+    ///
+    /// When `create`, `update` or `delete` fails with `Error::Network`, the write is appended to
+    /// the journal and reported as successful. `create` returns a temporary local id in `Meta`,
+    /// which can be used by later `update`s and `delete`s. Once a write is queued, later writes are
+    /// queued too to keep them in order, until `flush()` replays them. Reads aren't served from the
+    /// journal, and `create_bulk` isn't queued. Error responses aren't queued, including those
+    /// whose body isn't JSON, such as a gateway error page, which fail with `Error::Json`.
+    ///
+    /// The journal survives restarts: writes queued by a previous process are replayed by `flush()`.
+    ///
+    /// Any `Error::Network` is taken as the write not having reached the server. If it occurs
+    /// after the server accepted a `create`, e.g. while reading the response body, the create is
+    /// queued anyway and replaying it duplicates the record.
+    pub fn with_offline_journal<P: AsRef<Path>>(mut self, path: P) -> Client<'a> {
+        self.journal = Some(Journal::new(path.as_ref()));
+        self
+    }
+
+    /// Number of writes waiting in the journal.
+    pub fn pending_writes(&self) -> Result<usize> {
+        match &self.journal {
+            Some(journal) => Ok(journal.load()?.len()),
+            None => Ok(0),
+        }
+    }
+
+    /// Replay queued writes in order.
+    ///
+    /// Temporary ids in later writes are replaced by real ids as soon as creates succeed.
+    /// Replay stops at the first network error, leaving remaining writes in the journal.
+    pub fn flush(&self) -> Result<FlushReport> {
+        let mut report = FlushReport::default();
+        let journal = match &self.journal {
+            Some(journal) => journal,
+            None => return Ok(report),
+        };
+        let _lock = journal.lock();
+
+        let mut entries = journal.load()?;
+        while let Some(entry) = entries.front() {
+            let result = match entry {
+                Entry::Create { data, .. } => self
+                    .create_online::<Value>(data)
+                    .map(|(_, meta)| Some(meta.id)),
+                Entry::Update { id, data } => self.update_online(id, data).map(|_| None),
+                Entry::Delete { id } => self.delete_online(id).map(|_| None),
+            };
+            let entry = entries.pop_front().unwrap();
+            match result {
+                Err(Error::Network { .. }) => {
+                    entries.push_front(entry);
+                    break;
+                }
+                Err(err) => report.rejected.push((entry.id().to_string(), err)),
+                Ok(created) => {
+                    report.replayed += 1;
+                    if let Some(real_id) = created {
+                        for pending in entries.iter_mut() {
+                            pending.resolve(entry.id(), &real_id);
+                        }
+                        report.ids.insert(entry.id().to_string(), real_id);
+                    }
+                }
+            }
+            // Persist progress after every write, so a crash doesn't replay it twice.
+            journal.store(&entries)?;
+        }
+
+        report.pending = entries.len();
+        Ok(report)
+    }
+
+    pub(crate) fn create_offline<T>(&self, journal: &Journal, data: &T) -> Result<(T, Meta)>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        self.write_through(
+            journal,
+            || self.create_online(data),
+            || {
+                self.payload(data)?;
+                let value = to_value(data).context(error::Json { reason: "payload" })?;
+                let record = from_value(value.clone()).context(error::Json { reason: "data" })?;
+                let now = now_iso8601();
+                let meta = Meta {
+                    id: journal.local_id(),
+                    created_on: now.clone(),
+                    updated_on: now,
+                };
+                let entry = Entry::Create {
+                    local_id: meta.id.clone(),
+                    data: value,
+                };
+                Ok((entry, (record, meta)))
+            },
+        )
+    }
+
+    pub(crate) fn update_offline<T>(&self, journal: &Journal, id: &str, data: &T) -> Result<()>
+    where
+        T: Serialize,
+    {
+        self.write_through(
+            journal,
+            || self.update_online(id, data),
+            || {
+                self.payload(data)?;
+                let entry = Entry::Update {
+                    id: id.to_string(),
+                    data: to_value(data).context(error::Json { reason: "payload" })?,
+                };
+                Ok((entry, ()))
+            },
+        )
+    }
+
+    pub(crate) fn delete_offline(&self, journal: &Journal, id: &str) -> Result<()> {
+        self.write_through(
+            journal,
+            || self.delete_online(id),
+            || Ok((Entry::Delete { id: id.to_string() }, ())),
+        )
+    }
+
+    /// Run `online` unless writes are already queued, and queue the entry made by `offline`
+    /// instead if it fails with a network error.
+    fn write_through<R, N, F>(&self, journal: &Journal, online: N, offline: F) -> Result<R>
+    where
+        N: FnOnce() -> Result<R>,
+        F: FnOnce() -> Result<(Entry, R)>,
+    {
+        let pending = {
+            let _lock = journal.lock();
+            !journal.is_empty()?
+        };
+        // Writes may run concurrently, so the lock isn't held during the request.
+        if !pending {
+            match online() {
+                Err(Error::Network { .. }) => {}
+                result => return result,
+            }
+        }
+        let (entry, queued) = offline()?;
+        let _lock = journal.lock();
+        journal.append(&entry)?;
+        Ok(queued)
+    }
+}
+
+fn millis_since_epoch() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Current time formatted as jsonbox does, e.g. `2019-09-22T12:24:37.513Z`.
+fn now_iso8601() -> String {
+    format_millis(millis_since_epoch())
+}
+
+fn format_millis(millis: u64) -> String {
+    let secs = millis / 1000;
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let rem = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        millis % 1000
+    )
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) date in the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_millis() {
+        assert_eq!(format_millis(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(format_millis(1569155077513), "2019-09-22T12:24:37.513Z");
+        assert_eq!(format_millis(951782400000), "2000-02-29T00:00:00.000Z");
+    }
+
+    #[test]
+    fn test_entry_format() {
+        let entry = Entry::Update {
+            id: "local-1-0".into(),
+            data: json!({ "name": "kuy" }),
+        };
+        assert_eq!(
+            to_string(&entry).unwrap(),
+            r#"{"op":"update","id":"local-1-0","data":{"name":"kuy"}}"#
+        );
+
+        let mut entry = entry;
+        entry.resolve("local-1-0", "11111111111111111111");
+        assert_eq!(entry.id(), "11111111111111111111");
+    }
+}
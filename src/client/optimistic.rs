@@ -0,0 +1,84 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{from_value, to_value, Value};
+use snafu::ResultExt;
+
+use crate::client::value::{without_meta, META_KEYS};
+use crate::client::{Client, Operation};
+use crate::error::{self, Error, Result};
+
+/// Default number of retries of `Client::modify` on conflict.
+pub(crate) const CONFLICT_RETRIES: u32 = 3;
+
+impl<'a> Client<'a> {
+    /// Set how many times `modify` retries after a conflict. Default is 3.
+    pub fn with_conflict_retries(mut self, retries: u32) -> Client<'a> {
+        self.conflict_retries = retries;
+        self
+    }
+
+    /// Update a record only if it hasn't been modified since `expected_updated_on`.
+    ///
+    /// The record's `Meta::updated_on` is read right before the write, and `Error::Conflict` is
+    /// returned without writing if it differs. jsonbox has no conditional write, so this narrows
+    /// the window for lost updates but can't close it entirely.
+    pub fn update_if_unmodified<T>(
+        &self,
+        id: &str,
+        expected_updated_on: &str,
+        data: &T,
+    ) -> Result<()>
+    where
+        T: Serialize,
+    {
+        let (_, meta) = self.fetch_by_id::<Value>(id)?;
+        if meta.updated_on != expected_updated_on {
+            return Err(Error::Conflict {
+                id: id.to_string(),
+                expected: expected_updated_on.to_string(),
+                actual: meta.updated_on,
+            });
+        }
+        self.update(id, data)
+    }
+
+    /// Read a record, modify it with `f`, and write it back with `update_if_unmodified`.
+    ///
+    /// On conflict, the whole cycle is retried up to the number of times set by
+    /// `with_conflict_retries`, after which `Error::Conflict` is returned. `f` may be called
+    /// several times, and gets the record without meta keys, so `T` may be `serde_json::Value`.
+    /// Returns the record as written.
+    pub fn modify<T, F>(&self, id: &str, mut f: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnMut(&mut T),
+    {
+        let mut retries = 0;
+        loop {
+            let (data, meta) = self.fetch_by_id::<T>(id)?;
+            let mut data = drop_meta(data)?;
+            f(&mut data);
+            match self.update_if_unmodified(id, &meta.updated_on, &data) {
+                Err(Error::Conflict { .. }) if retries < self.conflict_retries => {
+                    retries += 1;
+                    self.metrics.retry(Operation::Update);
+                }
+                Err(err) => return Err(err),
+                Ok(()) => return Ok(data),
+            }
+        }
+    }
+}
+
+/// Drop meta keys, which can't be written back, from a record read as `T`.
+fn drop_meta<T>(data: T) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let value = to_value(&data).context(error::Json { reason: "data" })?;
+    match &value {
+        Value::Object(map) if META_KEYS.iter().any(|key| map.contains_key(*key)) => {
+            from_value(without_meta(&value)).context(error::Json { reason: "data" })
+        }
+        _ => Ok(data),
+    }
+}
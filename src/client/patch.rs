@@ -0,0 +1,51 @@
+use serde_json::Value;
+
+use crate::client::value::without_meta;
+use crate::client::Client;
+use crate::error::Result;
+use crate::patch::{apply_patch, merge_patch, PatchOperation};
+
+impl<'a> Client<'a> {
+    /// Partially update a record with a JSON Merge Patch ([RFC 7396](https://tools.ietf.org/html/rfc7396)).
+    ///
+    /// jsonbox only supports replacing whole records, so the record is read, merged on the client,
+    /// and written back with `update`. Meta keys in `merge_patch` are ignored. Returns the merged
+    /// record without meta keys.
+    ///
+    /// Note that a concurrent update between the read and the write is overwritten.
+    pub fn patch(&self, id: &str, merge_patch: &Value) -> Result<Value> {
+        let (record, _) = self.patch_record(id, merge_patch, false)?;
+        Ok(record)
+    }
+
+    /// Same as `patch`, but skip the write when the merge doesn't change the record.
+    /// Returns `None` if nothing was written.
+    pub fn patch_if_changed(&self, id: &str, merge_patch: &Value) -> Result<Option<Value>> {
+        let (record, written) = self.patch_record(id, merge_patch, true)?;
+        Ok(if written { Some(record) } else { None })
+    }
+
+    fn patch_record(&self, id: &str, patch: &Value, skip_unchanged: bool) -> Result<(Value, bool)> {
+        let (original, _) = self.read().id_value(id)?;
+        let mut record = original.clone();
+        merge_patch(&mut record, &without_meta(patch));
+        if skip_unchanged && record == original {
+            return Ok((record, false));
+        }
+        self.update(id, &record)?;
+        Ok((record, true))
+    }
+
+    /// Apply JSON Patch ([RFC 6902](https://tools.ietf.org/html/rfc6902)) operations to a record.
+    ///
+    /// The record is read, patched on the client, and written back with `update`. Paths are
+    /// relative to the record without meta keys. Nothing is written if an operation fails,
+    /// including a failed `test` operation reported as `Error::PatchTest`. Returns the patched
+    /// record without meta keys.
+    pub fn apply_patch(&self, id: &str, ops: &[PatchOperation]) -> Result<Value> {
+        let (mut record, _) = self.read().id_value(id)?;
+        apply_patch(&mut record, ops)?;
+        self.update_value(id, &record)?;
+        Ok(record)
+    }
+}
@@ -1,10 +1,14 @@
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use serde::de::DeserializeOwned;
+use serde_json::Value;
 use std::fmt;
 
 use crate::client::{Client, Meta};
 use crate::error::Result;
 
+/// Largest `limit` accepted by jsonbox.
+pub(in crate::client) const MAX_LIMIT: u32 = 1000;
+
 #[derive(Clone)]
 enum Order<'a> {
     Asc(&'a str),
@@ -15,22 +19,26 @@ enum Order<'a> {
 ///
 /// `QueryBuilder::new()` is not exposed. Use `Client::read()` to get a new instance of `QueryBuilder`.
 /// `format!("{:?}", q)` is useful to inspect current query string.
+#[derive(Clone)]
 pub struct QueryBuilder<'a> {
-    client: &'a Client<'a>,
+    pub(in crate::client) client: &'a Client<'a>,
     sort: Order<'a>,
     skip: u32,
     limit: u32,
     q: Vec<String>,
+    /// Bypass the cache of the client.
+    pub(in crate::client) fresh: bool,
 }
 
 impl<'a> QueryBuilder<'a> {
     pub(in crate::client) fn new(client: &'a Client) -> QueryBuilder<'a> {
         QueryBuilder {
             client,
-            sort: Order::Desc("_createdOn".into()),
+            sort: Order::Desc("_createdOn"),
             skip: 0,
             limit: 20,
             q: vec![],
+            fresh: false,
         }
     }
 
@@ -107,6 +115,31 @@ impl<'a> QueryBuilder<'a> {
         self.client.read_by_query(self)
     }
 
+    /// Iterate over pages of the query result, starting at the configured `skip`.
+    pub(in crate::client) fn pages(&self) -> Pages<'a> {
+        Pages {
+            query: self.clone(),
+            done: false,
+        }
+    }
+
+    /// Field of sort order, and whether it's descending.
+    pub(in crate::client) fn sort_key(&self) -> (&'a str, bool) {
+        match self.sort {
+            Order::Asc(field) => (field, false),
+            Order::Desc(field) => (field, true),
+        }
+    }
+
+    pub(in crate::client) fn page_size(&self) -> u32 {
+        self.limit
+    }
+
+    pub(in crate::client) fn offset(&self) -> u32 {
+        self.skip
+    }
+
+    #[allow(clippy::inherent_to_string)]
     pub(in crate::client) fn to_string(&self) -> String {
         let mut query = format!(
             "sort={}&skip={}&limit={}",
@@ -114,7 +147,7 @@ impl<'a> QueryBuilder<'a> {
             self.skip,
             self.limit
         );
-        if self.q.len() > 0 {
+        if !self.q.is_empty() {
             query = format!("{}&q={}", query, self.filter_string());
         }
         query
@@ -137,6 +170,30 @@ impl<'a> QueryBuilder<'a> {
     }
 }
 
+/// Iterator over pages of untyped records, fetched with `skip`/`limit`.
+pub(in crate::client) struct Pages<'a> {
+    query: QueryBuilder<'a>,
+    done: bool,
+}
+
+impl<'a> Iterator for Pages<'a> {
+    type Item = Result<Vec<(Value, Meta)>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let page = self.query.run_values();
+        match &page {
+            Ok(records) if records.len() as u32 == self.query.limit && self.query.limit > 0 => {
+                self.query.skip += self.query.limit;
+            }
+            _ => self.done = true,
+        }
+        Some(page)
+    }
+}
+
 impl<'a> fmt::Debug for QueryBuilder<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.to_string())
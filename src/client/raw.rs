@@ -0,0 +1,109 @@
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Serializer};
+use serde_json::from_str;
+use serde_json::value::RawValue;
+use snafu::ResultExt;
+use std::cell::OnceCell;
+
+use crate::client::{Meta, MetaRaw};
+use crate::error::{self, Result};
+use crate::QueryBuilder;
+
+/// A record kept as the JSON text sent by jsonbox, meta keys included.
+///
+/// Meta is parsed on first access only, and the payload is deserialized on demand, so records
+/// can be forwarded as they are. Serializing a `RawRecord` writes its text verbatim.
+#[derive(Debug)]
+pub struct RawRecord {
+    raw: Box<RawValue>,
+    meta: OnceCell<Meta>,
+}
+
+impl RawRecord {
+    fn new(raw: Box<RawValue>) -> RawRecord {
+        RawRecord {
+            raw,
+            meta: OnceCell::new(),
+        }
+    }
+
+    /// The JSON text of the record.
+    pub fn get(&self) -> &str {
+        self.raw.get()
+    }
+
+    pub fn into_raw(self) -> Box<RawValue> {
+        self.raw
+    }
+
+    /// Meta of the record, parsed on first call.
+    pub fn meta(&self) -> Result<&Meta> {
+        if let Some(meta) = self.meta.get() {
+            return Ok(meta);
+        }
+        let meta: MetaRaw = from_str(self.get()).context(error::Json { reason: "meta" })?;
+        Ok(self.meta.get_or_init(|| Meta::from(meta)))
+    }
+
+    /// Deserialize the record. Schemas and key escaping of the client aren't applied.
+    pub fn parse<T>(&self) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        from_str(self.get()).context(error::Json { reason: "data" })
+    }
+}
+
+impl Serialize for RawRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.raw.serialize(serializer)
+    }
+}
+
+impl<'a> QueryBuilder<'a> {
+    /// Get a single record by id without deserializing it.
+    pub fn id_raw(&self, id: &str) -> Result<RawRecord> {
+        self.client.read_by_id_with(id, |raw| {
+            let record = from_str(raw).context(error::Json { reason: "data" })?;
+            Ok(RawRecord::new(record))
+        })
+    }
+
+    /// Run query as `run()` does, returning records without deserializing them.
+    pub fn run_raw(&self) -> Result<Vec<RawRecord>> {
+        self.client.read_by_query_with(self, |raw| {
+            let records: Vec<Box<RawValue>> =
+                from_str(raw).context(error::Json { reason: "data" })?;
+            Ok(records.into_iter().map(RawRecord::new).collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, to_string, Value};
+
+    #[test]
+    fn test_raw_record() {
+        let text = r#"{"_id":"11111111111111111111","name":"kuy", "count":42,"_createdOn":"2019-09-22T12:24:37.513Z"}"#;
+        let record = RawRecord::new(from_str(text).unwrap());
+        assert!(record.meta.get().is_none());
+        assert_eq!(record.meta().unwrap().id, "11111111111111111111");
+        assert_eq!(
+            record.meta().unwrap().updated_on,
+            "2019-09-22T12:24:37.513Z"
+        );
+        assert_eq!(record.parse::<Value>().unwrap()["count"], json!(42));
+
+        // Forwarded byte for byte, whitespace included.
+        assert_eq!(to_string(&vec![record]).unwrap(), format!("[{}]", text));
+    }
+
+    #[test]
+    fn test_raw_record_without_meta() {
+        let record = RawRecord::new(from_str(r#"{"name":"kuy"}"#).unwrap());
+        assert!(record.meta().is_err());
+        assert!(record.parse::<Value>().is_ok());
+    }
+}
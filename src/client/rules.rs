@@ -0,0 +1,224 @@
+use serde::Serialize;
+use serde_json::{to_string, to_value, Map, Value};
+use snafu::ResultExt;
+use std::slice;
+
+use crate::client::{Client, SIZE_LIMIT};
+use crate::error::{self, Error, Result};
+
+/// Prefix added to escaped keys by `Client::with_key_escaping()`.
+const ESCAPE: char = '~';
+
+fn is_reserved(key: &str) -> bool {
+    key.starts_with('_') || key.starts_with('$')
+}
+
+/// Fail with `Error::PayloadTooLarge` if a serialized body exceeds jsonbox's size limit.
+pub(crate) fn check_size(body: &str) -> Result<()> {
+    if body.len() > SIZE_LIMIT {
+        return Err(Error::PayloadTooLarge {
+            size: body.len(),
+            limit: SIZE_LIMIT,
+        });
+    }
+    Ok(())
+}
+
+/// JSON pointers of all keys starting with `_` or `$`, at any depth, prefixed with `prefix`.
+fn reserved_keys(value: &Value, prefix: &str) -> Vec<String> {
+    let mut found = vec![];
+    collect_reserved_keys(value, prefix, &mut found);
+    found
+}
+
+fn collect_reserved_keys(value: &Value, path: &str, found: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = format!("{}/{}", path, key);
+                if is_reserved(key) {
+                    found.push(path.clone());
+                }
+                collect_reserved_keys(value, &path, found);
+            }
+        }
+        Value::Array(items) => {
+            for (i, value) in items.iter().enumerate() {
+                collect_reserved_keys(value, &format!("{}/{}", path, i), found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Prefix reserved keys, and keys already starting with the escape character, with `~`.
+pub(crate) fn escape_keys(value: Value) -> Value {
+    rename_keys(value, &|key| {
+        if is_reserved(&key) || key.starts_with(ESCAPE) {
+            format!("{}{}", ESCAPE, key)
+        } else {
+            key
+        }
+    })
+}
+
+/// Revert `escape_keys()`. Meta keys added by jsonbox are left as they are.
+pub(crate) fn unescape_keys(value: Value) -> Value {
+    rename_keys(value, &|key| match key.strip_prefix(ESCAPE) {
+        Some(key) => key.to_string(),
+        None => key,
+    })
+}
+
+fn rename_keys(value: Value, rename: &dyn Fn(String) -> String) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (rename(key), rename_keys(value, rename)))
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|value| rename_keys(value, rename))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+impl<'a> Client<'a> {
+    /// Escape keys starting with `_` or `$` by prefixing them with `~` on write, and unescape
+    /// them on read, instead of rejecting such payloads with `Error::ReservedKey`.
+    ///
+    /// Keys already starting with `~` get one more, so any key round-trips. Filters passed to
+    /// `QueryBuilder::filter_by()` aren't rewritten and must use escaped names.
+    pub fn with_key_escaping(mut self) -> Client<'a> {
+        self.escape_keys = true;
+        self
+    }
+
+    /// Serialize a payload after checking it locally: validators, reserved keys and size.
+    pub(crate) fn payload<T>(&self, data: &T) -> Result<String>
+    where
+        T: Serialize,
+    {
+        let body = self.encode(slice::from_ref(data), false)?.remove(0);
+        check_size(&body)?;
+        Ok(body)
+    }
+
+    /// Serialize records of a bulk payload one by one, checking all of them first.
+    /// Paths in errors are prefixed with the index of the record.
+    ///
+    /// The size is left to the caller, which knows how records are grouped into requests.
+    pub(crate) fn payloads<T>(&self, data: &[T]) -> Result<Vec<String>>
+    where
+        T: Serialize,
+    {
+        self.encode(data, true)
+    }
+
+    fn encode<T>(&self, data: &[T], indexed: bool) -> Result<Vec<String>>
+    where
+        T: Serialize,
+    {
+        let prefix = |i: usize| {
+            if indexed {
+                format!("/{}", i)
+            } else {
+                String::new()
+            }
+        };
+        let values = data
+            .iter()
+            .map(|record| to_value(record).context(error::Json { reason: "payload" }))
+            .collect::<Result<Vec<Value>>>()?;
+
+        let violations: Vec<_> = values
+            .iter()
+            .enumerate()
+            .flat_map(|(i, value)| self.violations(value, &prefix(i)))
+            .collect();
+        if !violations.is_empty() {
+            return Err(Error::Validation { violations });
+        }
+
+        if self.escape_keys {
+            return values
+                .into_iter()
+                .map(|value| {
+                    to_string(&escape_keys(value)).context(error::Json { reason: "payload" })
+                })
+                .collect();
+        }
+
+        let paths: Vec<String> = values
+            .iter()
+            .enumerate()
+            .flat_map(|(i, value)| reserved_keys(value, &prefix(i)))
+            .collect();
+        if !paths.is_empty() {
+            return Err(Error::ReservedKey { paths });
+        }
+
+        // Serialize `data` itself rather than `values` to keep the order of fields.
+        data.iter()
+            .map(|record| to_string(record).context(error::Json { reason: "payload" }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reserved_keys() {
+        let value = json!({
+            "name": "kuy",
+            "_id": "x",
+            "tags": [{ "$set": 1 }],
+            "nested": { "_kept": false },
+        });
+        assert_eq!(
+            reserved_keys(&value, "/0"),
+            vec!["/0/_id", "/0/nested/_kept", "/0/tags/0/$set"]
+        );
+        assert!(reserved_keys(&json!({ "name": "_ok" }), "").is_empty());
+    }
+
+    #[test]
+    fn test_check_size() {
+        assert!(check_size(&"x".repeat(SIZE_LIMIT)).is_ok());
+        match check_size(&"x".repeat(SIZE_LIMIT + 1)) {
+            Err(Error::PayloadTooLarge { size, limit }) => {
+                assert_eq!((size, limit), (SIZE_LIMIT + 1, SIZE_LIMIT))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_escape_round_trip() {
+        let value = json!({
+            "_private": 1,
+            "$price": [{ "_x": 2 }],
+            "~tilde": 3,
+            "plain": "_value",
+        });
+        let escaped = escape_keys(value.clone());
+        assert_eq!(
+            escaped,
+            json!({
+                "~_private": 1,
+                "~$price": [{ "~_x": 2 }],
+                "~~tilde": 3,
+                "plain": "_value",
+            })
+        );
+        assert!(reserved_keys(&escaped, "").is_empty());
+        assert_eq!(unescape_keys(escaped), value);
+    }
+}
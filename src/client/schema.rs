@@ -0,0 +1,166 @@
+use serde_json::Value;
+use std::any::type_name;
+
+use crate::client::migrate::{MigrationOptions, MigrationReport};
+use crate::client::Client;
+use crate::error::{Error, Result};
+
+/// A record type whose stored shape evolves over versions.
+///
+/// The version of a stored record is kept in `VERSION_FIELD` (a record without it is version 0),
+/// and `upgrades()[n]` turns a record of version `n` into version `n + 1`. The latest version is
+/// the number of upgrades. The field is updated by the client after each upgrade, so upgrade
+/// functions only have to reshape the record.
+///
+/// ```ignore
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     schema_version: u64,
+///     full_name: String,
+/// }
+///
+/// impl Versioned for User {
+///     fn upgrades() -> Vec<fn(Value) -> Value> {
+///         vec![|mut v| {
+///             v["full_name"] = v["name"].take();
+///             v
+///         }]
+///     }
+/// }
+///
+/// let client = Client::new("<BOX_ID>").with_schema::<User>();
+/// ```
+pub trait Versioned {
+    /// Field holding the schema version of a record.
+    const VERSION_FIELD: &'static str = "schema_version";
+
+    /// Ordered upgrade functions from version 0.
+    fn upgrades() -> Vec<fn(Value) -> Value>;
+}
+
+/// Upgrade path of a record type registered with `Client::with_schema`.
+pub(crate) struct Schema {
+    name: &'static str,
+    field: &'static str,
+    upgrades: Vec<fn(Value) -> Value>,
+}
+
+impl Schema {
+    pub(crate) fn of<T: Versioned>() -> Schema {
+        Schema {
+            name: type_name::<T>(),
+            field: T::VERSION_FIELD,
+            upgrades: T::upgrades(),
+        }
+    }
+
+    /// Upgrade a record to the latest version.
+    pub(crate) fn upgrade(&self, mut record: Value) -> Result<Value> {
+        let latest = self.upgrades.len() as u64;
+        let version = match record.get(self.field) {
+            None | Some(Value::Null) => 0,
+            Some(version) => match version.as_u64() {
+                Some(version) if version <= latest => version,
+                _ => {
+                    return Err(Error::UnsupportedVersion {
+                        found: version.to_string(),
+                        latest,
+                    })
+                }
+            },
+        };
+        for upgrade in &self.upgrades[version as usize..] {
+            record = upgrade(record);
+        }
+        if let Value::Object(map) = &mut record {
+            map.insert(self.field.to_string(), Value::from(latest));
+        }
+        Ok(record)
+    }
+}
+
+impl<'a> Client<'a> {
+    /// Register a versioned record type.
+    ///
+    /// Records read as `T` through this client (e.g. with `QueryBuilder::id::<T>()` or
+    /// `QueryBuilder::run::<T>()`) are upgraded to the latest version before deserializing.
+    /// Upgrades aren't written back, use `migrate_box` to persist them.
+    pub fn with_schema<T: Versioned>(mut self) -> Client<'a> {
+        let schema = Schema::of::<T>();
+        self.schemas.retain(|s| s.name != schema.name);
+        self.schemas.push(schema);
+        self
+    }
+
+    pub(crate) fn schema_of<T>(&self) -> Option<&Schema> {
+        let name = type_name::<T>();
+        self.schemas.iter().find(|schema| schema.name == name)
+    }
+
+    /// Upgrade every record of the box to the latest version of `T` and write them back.
+    ///
+    /// Records already at the latest version are left untouched. See
+    /// `QueryBuilder::for_each_update` for how interruptions are reported.
+    pub fn migrate_box<T: Versioned>(&self) -> Result<MigrationReport> {
+        let schema = Schema::of::<T>();
+        self.read()
+            .limit(100)
+            .migrate(&MigrationOptions::default(), |record| {
+                schema.upgrade(record.clone())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matches::assert_matches;
+    use serde_json::json;
+
+    struct User;
+
+    impl Versioned for User {
+        fn upgrades() -> Vec<fn(Value) -> Value> {
+            vec![
+                |mut v| {
+                    v["full_name"] = v["name"].take();
+                    v.as_object_mut().unwrap().remove("name");
+                    v
+                },
+                |mut v| {
+                    v["tags"] = json!([]);
+                    v
+                },
+            ]
+        }
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let schema = Schema::of::<User>();
+        assert_eq!(
+            schema.upgrade(json!({"name":"kuy"})).unwrap(),
+            json!({"full_name":"kuy","tags":[],"schema_version":2})
+        );
+        assert_eq!(
+            schema
+                .upgrade(json!({"full_name":"kuy","schema_version":1}))
+                .unwrap(),
+            json!({"full_name":"kuy","tags":[],"schema_version":2})
+        );
+        assert_eq!(
+            schema
+                .upgrade(json!({"full_name":"kuy","tags":["a"],"schema_version":2}))
+                .unwrap(),
+            json!({"full_name":"kuy","tags":["a"],"schema_version":2})
+        );
+        assert_matches!(
+            schema.upgrade(json!({"schema_version":3})),
+            Err(Error::UnsupportedVersion { latest: 2, .. })
+        );
+        assert_matches!(
+            schema.upgrade(json!({"schema_version":"1"})),
+            Err(Error::UnsupportedVersion { .. })
+        );
+    }
+}
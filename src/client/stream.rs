@@ -0,0 +1,218 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{from_value, Deserializer, Value};
+use snafu::ResultExt;
+use std::io::{self, BufRead, BufReader, Read};
+use std::marker::PhantomData;
+use std::time::Instant;
+
+use crate::client::{status_error, Client, Meta, MetaRaw, Operation};
+use crate::error::{self, Error, Result};
+use crate::trace;
+use crate::url;
+use crate::QueryBuilder;
+
+/// Records of a query, deserialized one at a time as the response body is read.
+/// Use `QueryBuilder::stream()` to get it.
+///
+/// The iterator ends after the first error.
+pub struct RecordStream<'a, T> {
+    client: &'a Client<'a>,
+    reader: BufReader<Box<dyn Read>>,
+    started: bool,
+    done: bool,
+    record: PhantomData<fn() -> T>,
+}
+
+impl<'a> QueryBuilder<'a> {
+    /// Run query, returning an iterator deserializing records while the response is read.
+    ///
+    /// Only one record is held in memory at a time, so this suits queries with a huge `limit`.
+    /// The cache is bypassed. If the client has middlewares, the request goes through them and
+    /// the whole body is buffered first, since middlewares see it as a string.
+    ///
+    /// ```ignore
+    /// let mut query = client.read();
+    /// query.limit(1000);
+    /// for record in query.stream::<Data>()? {
+    ///     let (data, meta) = record?;
+    ///     println!("{}: {:?}", meta.id, data);
+    /// }
+    /// ```
+    pub fn stream<T>(&self) -> Result<RecordStream<'a, T>>
+    where
+        T: DeserializeOwned,
+    {
+        let client = self.client;
+        let reader = client.open_query(self)?;
+        Ok(RecordStream {
+            client,
+            reader: BufReader::new(reader),
+            started: false,
+            done: false,
+            record: PhantomData,
+        })
+    }
+}
+
+impl<'a> Client<'a> {
+    /// Send a query and return a reader of the response body.
+    fn open_query(&self, query: &QueryBuilder) -> Result<Box<dyn Read>> {
+        let query = query.to_string();
+        self.observe(Operation::ReadByQuery, None, Some(&query), || {
+            let url = url::of_query(self.base_url, self.box_id, &query);
+            if !self.middlewares.is_empty() {
+                let raw = self.send(Operation::ReadByQuery, &url, None)?;
+                return Ok(Box::new(io::Cursor::new(raw.into_bytes())) as Box<dyn Read>);
+            }
+
+            let started = Instant::now();
+            let mut res = match reqwest::Client::new().get(&url).send() {
+                Ok(res) => res,
+                Err(source) => {
+                    let err = Error::Network { source };
+                    trace::failed(&err, started.elapsed());
+                    return Err(err);
+                }
+            };
+            let status = res.status().as_u16();
+            let size = res.content_length().unwrap_or_default() as usize;
+            trace::completed(status, size, started.elapsed());
+            if !res.status().is_success() {
+                let body = res.text().context(error::Network {})?;
+                return Err(status_error(status, body));
+            }
+            Ok(Box::new(res))
+        })
+    }
+}
+
+impl<'a, T> RecordStream<'a, T>
+where
+    T: DeserializeOwned,
+{
+    /// Read the next record of the JSON array, or `None` at its end.
+    fn read_record(&mut self) -> Result<Option<(T, Meta)>> {
+        if !self.started {
+            self.expect(b'[')?;
+            self.started = true;
+            if self.peek()? == Some(b']') {
+                self.reader.consume(1);
+                return Ok(None);
+            }
+        } else {
+            match self.peek()? {
+                Some(b',') => self.reader.consume(1),
+                Some(b']') => {
+                    self.reader.consume(1);
+                    return Ok(None);
+                }
+                _ => return Err(malformed()),
+            }
+        }
+
+        // Records are objects, so the deserializer stops right after the closing brace.
+        let record = Value::deserialize(&mut Deserializer::from_reader(&mut self.reader))
+            .context(error::Json { reason: "data" })?;
+        let meta = MetaRaw::deserialize(&record).context(error::Json { reason: "meta" })?;
+        let data = from_value(self.client.restore::<T>(record)?)
+            .context(error::Json { reason: "data" })?;
+        Ok(Some((data, Meta::from(meta))))
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        if self.peek()? != Some(byte) {
+            return Err(malformed());
+        }
+        self.reader.consume(1);
+        Ok(())
+    }
+
+    /// Skip whitespace and return the next byte without consuming it.
+    fn peek(&mut self) -> Result<Option<u8>> {
+        loop {
+            let buf = self.reader.fill_buf().context(error::Io {})?;
+            match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+                Some(i) => {
+                    let byte = buf[i];
+                    self.reader.consume(i);
+                    return Ok(Some(byte));
+                }
+                None if buf.is_empty() => return Ok(None),
+                None => {
+                    let len = buf.len();
+                    self.reader.consume(len);
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for RecordStream<'a, T>
+where
+    T: DeserializeOwned,
+{
+    type Item = Result<(T, Meta)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.read_record().transpose();
+        if !matches!(record, Some(Ok(_))) {
+            self.done = true;
+        }
+        record
+    }
+}
+
+fn malformed() -> Error {
+    Error::Json {
+        reason: "data".to_string(),
+        source: serde::de::Error::custom("expected an array of records"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream<'a>(client: &'a Client<'a>, body: &str) -> RecordStream<'a, Value> {
+        let reader: Box<dyn Read> = Box::new(io::Cursor::new(body.as_bytes().to_vec()));
+        RecordStream {
+            client,
+            reader: BufReader::with_capacity(4, reader),
+            started: false,
+            done: false,
+            record: PhantomData,
+        }
+    }
+
+    #[test]
+    fn test_read_records() {
+        let client = Client::new("xxx");
+        let body = r#" [ {"_id":"a","_createdOn":"2019-09-22T12:00:00.000Z","n":[1, 2]} ,
+            {"_id":"b","_createdOn":"2019-09-22T12:00:00.000Z","n":"]"} ] "#;
+        let records: Vec<_> = stream(&client, body).collect::<Result<_>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].0["n"][1], 2);
+        assert_eq!(records[1].1.id, "b");
+
+        assert_eq!(stream(&client, "[]").count(), 0);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let client = Client::new("xxx");
+        let body = r#"[{"_id":"a","_createdOn":"2019-09-22T12:00:00.000Z"} {"_id":"b"}]"#;
+        let mut records = stream(&client, body);
+        assert!(records.next().unwrap().is_ok());
+        assert!(records.next().unwrap().is_err());
+        assert!(records.next().is_none());
+
+        assert!(stream(&client, r#"{"message":"x"}"#)
+            .next()
+            .unwrap()
+            .is_err());
+    }
+}
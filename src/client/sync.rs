@@ -0,0 +1,341 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::client::bulk::BulkReport;
+use crate::client::{Client, Meta};
+use crate::error::{Error, Result};
+
+/// How records of two boxes are matched.
+#[derive(Clone, Debug)]
+pub enum MatchBy {
+    /// By `_id`. jsonbox assigns new ids on create, so a sync by id only updates records
+    /// found in both boxes, and reports the others as unmatched.
+    Id,
+    /// By the value of a payload field, e.g. a natural key such as `email`.
+    Key(String),
+}
+
+/// A side of a sync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Source,
+    Target,
+}
+
+type Resolver = dyn Fn(&(Value, Meta), &(Value, Meta)) -> Option<Side>;
+
+/// How a conflict is resolved, given the source and target versions of a record.
+pub enum Resolution {
+    /// The version with the later `updated_on` wins, the source one on a tie.
+    LastWriterWins,
+    /// The source version always wins.
+    SourceWins,
+    /// A closure picks the winning side, or `None` to leave both versions as they are.
+    Custom(Box<Resolver>),
+}
+
+/// Options of `Client::sync_plan()` and `Client::sync()`.
+pub struct SyncOptions {
+    match_by: MatchBy,
+    resolution: Resolution,
+    since: Option<String>,
+}
+
+impl SyncOptions {
+    /// Match records by a payload field. Resolve conflicts with `LastWriterWins` by default.
+    pub fn by_key(field: &str) -> SyncOptions {
+        SyncOptions {
+            match_by: MatchBy::Key(field.to_string()),
+            resolution: Resolution::LastWriterWins,
+            since: None,
+        }
+    }
+
+    /// Match records by `_id`, only updating records found in both boxes. Records found in one
+    /// box only are reported as unmatched, since a copy would get a new id and never match.
+    /// Resolve conflicts with `LastWriterWins` by default.
+    pub fn by_id() -> SyncOptions {
+        SyncOptions {
+            match_by: MatchBy::Id,
+            resolution: Resolution::LastWriterWins,
+            since: None,
+        }
+    }
+
+    /// Set how conflicts are resolved.
+    pub fn resolution(mut self, resolution: Resolution) -> SyncOptions {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Resolve conflicts with a closure. Shorthand of `resolution(Resolution::Custom(...))`.
+    pub fn resolve_with<F>(self, f: F) -> SyncOptions
+    where
+        F: Fn(&(Value, Meta), &(Value, Meta)) -> Option<Side> + 'static,
+    {
+        self.resolution(Resolution::Custom(Box::new(f)))
+    }
+
+    /// Set the time of the previous sync, as an `updated_on` timestamp.
+    ///
+    /// A record differing between boxes is then copied from the side which changed since,
+    /// and only considered a conflict if both did. Without it, every difference is a conflict.
+    pub fn since(mut self, timestamp: &str) -> SyncOptions {
+        self.since = Some(timestamp.to_string());
+        self
+    }
+}
+
+/// A write planned by a sync, to the box on side `to`. `key` is the matching key of the record.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum SyncAction {
+    Create {
+        to: Side,
+        key: String,
+        data: Value,
+    },
+    Update {
+        to: Side,
+        key: String,
+        id: String,
+        data: Value,
+    },
+}
+
+/// Writes needed to bring two boxes in step, made by `Client::sync_plan()`.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SyncPlan {
+    pub actions: Vec<SyncAction>,
+    /// Keys of conflicting records left as they are by the resolution.
+    pub unresolved: Vec<String>,
+    /// Ids of records without the key field, which can't be matched. When matching by id,
+    /// ids of records found in one box only.
+    pub unmatched: Vec<String>,
+}
+
+impl SyncPlan {
+    /// Whether the boxes are already in step.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Apply planned writes. Failures don't stop other writes, and are reported by matching key.
+    pub fn apply(&self, source: &Client, target: &Client) -> BulkReport {
+        let results = self
+            .actions
+            .iter()
+            .map(|action| match action {
+                SyncAction::Create { to, key, data } => {
+                    let client = if *to == Side::Source { source } else { target };
+                    (key.clone(), client.create::<Value>(data).map(|_| ()))
+                }
+                SyncAction::Update { to, key, id, data } => {
+                    let client = if *to == Side::Source { source } else { target };
+                    (key.clone(), client.update(id, data))
+                }
+            })
+            .collect();
+        BulkReport::collect(results)
+    }
+}
+
+impl<'a> Client<'a> {
+    /// Plan writes to bring this box (the source) and `target` in step, without writing.
+    ///
+    /// Records found on one side only are created on the other, unless matching by id.
+    /// Records differing in payload are copied in the direction decided by `SyncOptions::since()`
+    /// and the resolution. Deletions can't be told apart from creations, so records are never
+    /// deleted.
+    pub fn sync_plan(&self, target: &Client, options: &SyncOptions) -> Result<SyncPlan> {
+        let mut plan = SyncPlan::default();
+        let source = index(self.all_records()?, &options.match_by, &mut plan.unmatched)?;
+        let mut target = index(
+            target.all_records()?,
+            &options.match_by,
+            &mut plan.unmatched,
+        )?;
+
+        let by_id = matches!(options.match_by, MatchBy::Id);
+        for (key, src) in source {
+            let tgt = match target.remove(&key) {
+                Some(tgt) => tgt,
+                None if by_id => {
+                    plan.unmatched.push(key);
+                    continue;
+                }
+                None => {
+                    plan.actions.push(SyncAction::Create {
+                        to: Side::Target,
+                        key,
+                        data: src.0,
+                    });
+                    continue;
+                }
+            };
+            if src.0 == tgt.0 {
+                continue;
+            }
+            match options.winner(&src, &tgt) {
+                Some(Side::Source) => plan.actions.push(SyncAction::Update {
+                    to: Side::Target,
+                    key,
+                    id: tgt.1.id,
+                    data: src.0,
+                }),
+                Some(Side::Target) => plan.actions.push(SyncAction::Update {
+                    to: Side::Source,
+                    key,
+                    id: src.1.id,
+                    data: tgt.0,
+                }),
+                None => plan.unresolved.push(key),
+            }
+        }
+        for (key, tgt) in target {
+            if by_id {
+                plan.unmatched.push(key);
+                continue;
+            }
+            plan.actions.push(SyncAction::Create {
+                to: Side::Source,
+                key,
+                data: tgt.0,
+            });
+        }
+        Ok(plan)
+    }
+
+    /// Plan and apply a sync in one go. See `sync_plan()`.
+    pub fn sync(&self, target: &Client, options: &SyncOptions) -> Result<BulkReport> {
+        Ok(self.sync_plan(target, options)?.apply(self, target))
+    }
+
+    /// Read all records of the box without meta keys, bypassing the cache.
+    pub(in crate::client) fn all_records(&self) -> Result<Vec<(Value, Meta)>> {
+        let mut query = self.read();
+        query.order_by("_createdOn").limit(1000);
+        query.fresh = true;
+        let mut records = vec![];
+        for page in query.pages() {
+            records.extend(page?);
+        }
+        Ok(records)
+    }
+}
+
+impl SyncOptions {
+    fn winner(&self, src: &(Value, Meta), tgt: &(Value, Meta)) -> Option<Side> {
+        if let Some(since) = &self.since {
+            let src_changed = src.1.updated_on > *since;
+            let tgt_changed = tgt.1.updated_on > *since;
+            if src_changed != tgt_changed {
+                return Some(if src_changed {
+                    Side::Source
+                } else {
+                    Side::Target
+                });
+            }
+        }
+        match &self.resolution {
+            Resolution::LastWriterWins if tgt.1.updated_on > src.1.updated_on => Some(Side::Target),
+            Resolution::LastWriterWins | Resolution::SourceWins => Some(Side::Source),
+            Resolution::Custom(f) => f(src, tgt),
+        }
+    }
+}
+
+/// Index records by matching key, failing with `Error::Duplicate` if a key isn't unique.
+pub(in crate::client) fn index(
+    records: Vec<(Value, Meta)>,
+    match_by: &MatchBy,
+    unmatched: &mut Vec<String>,
+) -> Result<BTreeMap<String, (Value, Meta)>> {
+    let mut index: BTreeMap<String, (Value, Meta)> = BTreeMap::new();
+    for (record, meta) in records {
+        let key = match match_by {
+            MatchBy::Id => meta.id.clone(),
+            MatchBy::Key(field) => match key_of(&record[field.as_str()]) {
+                Some(key) => key,
+                None => {
+                    unmatched.push(meta.id);
+                    continue;
+                }
+            },
+        };
+        if let Some((_, other)) = index.get(&key) {
+            let field = match match_by {
+                MatchBy::Id => "_id".to_string(),
+                MatchBy::Key(field) => field.clone(),
+            };
+            return Err(Error::Duplicate {
+                field,
+                ids: vec![other.id.clone(), meta.id],
+                value: key,
+            });
+        }
+        index.insert(key, (record, meta));
+    }
+    Ok(index)
+}
+
+fn key_of(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn version(data: Value, updated_on: &str) -> (Value, Meta) {
+        let meta = Meta {
+            id: "11111111111111111111".into(),
+            created_on: "2019-09-22T12:00:00.000Z".into(),
+            updated_on: updated_on.into(),
+        };
+        (data, meta)
+    }
+
+    #[test]
+    fn test_winner() {
+        let old = version(json!({ "n": 1 }), "2019-09-22T12:00:00.000Z");
+        let new = version(json!({ "n": 2 }), "2019-09-22T13:00:00.000Z");
+
+        let lww = SyncOptions::by_id();
+        assert_eq!(lww.winner(&old, &new), Some(Side::Target));
+        assert_eq!(lww.winner(&new, &old), Some(Side::Source));
+
+        let source = SyncOptions::by_id().resolution(Resolution::SourceWins);
+        assert_eq!(source.winner(&old, &new), Some(Side::Source));
+
+        // Only the source changed since the last sync, so no conflict to resolve.
+        let since = SyncOptions::by_id()
+            .resolve_with(|_, _| None)
+            .since("2019-09-22T12:30:00.000Z");
+        assert_eq!(since.winner(&new, &old), Some(Side::Source));
+        assert_eq!(since.winner(&new, &new), None);
+    }
+
+    #[test]
+    fn test_index() {
+        let records = vec![
+            version(
+                json!({ "email": "a@example.com" }),
+                "2019-09-22T12:00:00.000Z",
+            ),
+            version(json!({ "name": "no key" }), "2019-09-22T12:00:00.000Z"),
+        ];
+        let mut unmatched = vec![];
+        let index = index(records, &MatchBy::Key("email".into()), &mut unmatched).unwrap();
+        assert_eq!(index.keys().collect::<Vec<_>>(), vec!["a@example.com"]);
+        assert_eq!(unmatched, vec!["11111111111111111111"]);
+    }
+}
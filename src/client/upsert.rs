@@ -0,0 +1,76 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{to_value, Value};
+use snafu::ResultExt;
+
+use crate::client::{Client, Meta};
+use crate::error::{self, Error, Result};
+
+/// Outcome of `Client::upsert_by`.
+#[derive(Debug)]
+pub enum Upsert {
+    /// No record matched, a new one was created.
+    Created(Meta),
+    /// The record with this id matched and was replaced.
+    Updated(String),
+}
+
+impl<'a> Client<'a> {
+    /// Create or update a record identified by a natural key instead of `_id`.
+    ///
+    /// `value` must serialize to a string, a number or a boolean. Records are looked up with
+    /// `filter_by("<field>:{}", value)`, or `filter_by("<field>:={}", value)` for a number since
+    /// jsonbox matches `<field>:42` as a string. If exactly one record has `field` equal to `value`
+    /// it's replaced with `data`, if none has, `data` is created. If more than one matches,
+    /// `Error::Duplicate` is returned with their ids and nothing is written. Like other
+    /// read-then-write helpers, this isn't atomic.
+    pub fn upsert_by<V, T>(&self, field: &str, value: V, data: &T) -> Result<Upsert>
+    where
+        V: Serialize,
+        T: Serialize + DeserializeOwned,
+    {
+        let key = to_value(value).context(error::Json { reason: "key" })?;
+        let (filter, value) = match &key {
+            Value::String(s) => (format!("{}:{{}}", field), s.clone()),
+            Value::Number(n) => (format!("{}:={{}}", field), n.to_string()),
+            Value::Bool(b) => (format!("{}:{{}}", field), b.to_string()),
+            _ => {
+                return Err(Error::Json {
+                    reason: "key".to_string(),
+                    source: serde::ser::Error::custom("expected a string, a number or a boolean"),
+                })
+            }
+        };
+        let found = self
+            .read()
+            .filter_by(&filter, &value)
+            .limit(1000)
+            .run::<Value>()?;
+
+        // Filters of jsonbox are looser than equality (e.g. case-insensitive), so check again.
+        let ids: Vec<String> = found
+            .into_iter()
+            .filter(|(record, _)| match (&record[field], &key) {
+                (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+                (field, key) => field == key,
+            })
+            .map(|(_, meta)| meta.id)
+            .collect();
+
+        match ids.len() {
+            0 => {
+                let (_, meta) = self.create(data)?;
+                Ok(Upsert::Created(meta))
+            }
+            1 => {
+                let id = ids.into_iter().next().unwrap();
+                self.update(&id, data)?;
+                Ok(Upsert::Updated(id))
+            }
+            _ => Err(Error::Duplicate {
+                field: field.to_string(),
+                value,
+                ids,
+            }),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use serde_json::Value;
+use std::fmt;
+
+use crate::client::Client;
+#[cfg(feature = "json-schema")]
+use crate::error::{Error, Result};
+
+/// A violation found by a `Validator`, located by a JSON pointer into the payload.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub path: String,
+    pub message: String,
+}
+
+impl Violation {
+    pub fn new<P: Into<String>, M: Into<String>>(path: P, message: M) -> Violation {
+        Violation {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// A check of outgoing payloads, run by `create`, `create_bulk` and `update` before sending.
+///
+/// Implemented for closures, so a hand-written check can be registered as is:
+///
+/// ```ignore
+/// let client = Client::new("<BOX_ID>").with_validator(|payload: &Value| {
+///     match payload.get("name") {
+///         Some(Value::String(_)) => vec![],
+///         _ => vec![Violation::new("/name", "must be a string")],
+///     }
+/// });
+/// ```
+pub trait Validator: Send + Sync {
+    /// Return every violation found in `payload`, or an empty `Vec` if it's valid.
+    fn validate(&self, payload: &Value) -> Vec<Violation>;
+}
+
+impl<F> Validator for F
+where
+    F: Fn(&Value) -> Vec<Violation> + Send + Sync,
+{
+    fn validate(&self, payload: &Value) -> Vec<Violation> {
+        self(payload)
+    }
+}
+
+/// A `Validator` checking payloads against a JSON Schema.
+#[cfg(feature = "json-schema")]
+pub struct JsonSchema {
+    schema: jsonschema::JSONSchema,
+}
+
+#[cfg(feature = "json-schema")]
+impl JsonSchema {
+    /// Compile a JSON Schema. An invalid schema is reported as `Error::Validation`
+    /// pointing into the schema itself.
+    pub fn new(schema: &Value) -> Result<JsonSchema> {
+        let schema = jsonschema::JSONSchema::compile(schema).map_err(|err| Error::Validation {
+            violations: vec![Violation::new(
+                err.schema_path.to_string(),
+                format!("invalid schema: {}", err),
+            )],
+        })?;
+        Ok(JsonSchema { schema })
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl Validator for JsonSchema {
+    fn validate(&self, payload: &Value) -> Vec<Violation> {
+        match self.schema.validate(payload) {
+            Ok(()) => vec![],
+            Err(errors) => errors
+                .map(|err| Violation::new(err.instance_path.to_string(), err.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl<'a> Client<'a> {
+    /// Append a validator. Payloads are checked by all validators before any request is sent,
+    /// and rejected with `Error::Validation` listing every violation.
+    pub fn with_validator<V>(mut self, validator: V) -> Client<'a>
+    where
+        V: Validator + 'static,
+    {
+        self.validators.push(Box::new(validator));
+        self
+    }
+
+    /// Run all validators on a payload, prefixing paths of violations with `prefix`.
+    pub(crate) fn violations(&self, value: &Value, prefix: &str) -> Vec<Violation> {
+        self.validators
+            .iter()
+            .flat_map(|validator| validator.validate(value))
+            .map(|violation| Violation {
+                path: format!("{}{}", prefix, violation.path),
+                message: violation.message,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use serde_json::json;
+
+    #[test]
+    fn test_payloads() {
+        let client = Client::new("01234012340123401234").with_validator(|v: &Value| {
+            if v["count"].is_number() {
+                vec![]
+            } else {
+                vec![Violation::new("/count", "must be a number")]
+            }
+        });
+        let data = vec![json!({ "count": 1 }), json!({}), json!({ "count": "2" })];
+        match client.payloads(&data) {
+            Err(Error::Validation { violations }) => assert_eq!(
+                violations,
+                vec![
+                    Violation::new("/1/count", "must be a number"),
+                    Violation::new("/2/count", "must be a number"),
+                ]
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            client.payloads(&data[..1]).unwrap(),
+            vec![r#"{"count":1}"#.to_string()]
+        );
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[test]
+    fn test_json_schema() {
+        let schema = JsonSchema::new(&json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string" },
+                "count": { "type": "integer", "minimum": 0 },
+            },
+        }))
+        .unwrap();
+        assert!(schema
+            .validate(&json!({ "name": "kuy", "count": 1 }))
+            .is_empty());
+
+        let paths: Vec<String> = schema
+            .validate(&json!({ "count": -1 }))
+            .into_iter()
+            .map(|v| v.path)
+            .collect();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&"".to_string()));
+        assert!(paths.contains(&"/count".to_string()));
+    }
+}
@@ -0,0 +1,109 @@
+use serde_json::{Map, Value};
+
+use crate::client::{Client, Meta};
+use crate::error::Result;
+use crate::QueryBuilder;
+
+/// Keys added by jsonbox to every record.
+pub(crate) const META_KEYS: [&str; 3] = ["_id", "_createdOn", "_updatedOn"];
+
+/// Remove meta keys (`_id`, `_createdOn` and `_updatedOn`) from a record.
+pub fn strip_meta(value: &mut Value) {
+    if let Value::Object(map) = value {
+        for key in META_KEYS.iter() {
+            map.remove(*key);
+        }
+    }
+}
+
+/// Return a copy of a record without meta keys.
+pub(crate) fn without_meta(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(key, _)| !META_KEYS.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect::<Map<String, Value>>(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+impl<'a> Client<'a> {
+    /// Create a record from an untyped JSON object.
+    ///
+    /// Meta keys in `data` are ignored. The returned record doesn't contain them either,
+    /// they're available in `Meta` instead.
+    pub fn create_value(&self, data: &Value) -> Result<(Value, Meta)> {
+        let (mut record, meta) = self.create::<Value>(&without_meta(data))?;
+        strip_meta(&mut record);
+        Ok((record, meta))
+    }
+
+    /// Replace a record with an untyped JSON object.
+    ///
+    /// Meta keys in `data` are stripped before sending, so a record read by
+    /// `QueryBuilder::id::<Value>()` can be modified and written back as is.
+    pub fn update_value(&self, id: &str, data: &Value) -> Result<()> {
+        self.update(id, &without_meta(data))
+    }
+}
+
+impl<'a> QueryBuilder<'a> {
+    /// Get a single record by id as an untyped JSON object without meta keys.
+    pub fn id_value(&self, id: &str) -> Result<(Value, Meta)> {
+        let (mut record, meta) = self.id::<Value>(id)?;
+        strip_meta(&mut record);
+        Ok((record, meta))
+    }
+
+    /// Get all records with default query parameters as untyped JSON objects without meta keys.
+    pub fn all_values(&self) -> Result<Vec<(Value, Meta)>> {
+        Ok(strip_all(self.all::<Value>()?))
+    }
+
+    /// Run query as `run()` does, returning untyped JSON objects without meta keys.
+    pub fn run_values(&self) -> Result<Vec<(Value, Meta)>> {
+        Ok(strip_all(self.run::<Value>()?))
+    }
+}
+
+fn strip_all(records: Vec<(Value, Meta)>) -> Vec<(Value, Meta)> {
+    records
+        .into_iter()
+        .map(|(mut record, meta)| {
+            strip_meta(&mut record);
+            (record, meta)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_strip_meta() {
+        let mut value = json!({
+            "_id": "11111111111111111111",
+            "_createdOn": "2019-09-22T12:24:37.513Z",
+            "_updatedOn": "2019-09-22T12:25:52.114Z",
+            "name": "kuy",
+            "nested": { "_id": "kept" },
+        });
+        strip_meta(&mut value);
+        assert_eq!(value, json!({ "name": "kuy", "nested": { "_id": "kept" } }));
+
+        let mut value = json!([1, 2]);
+        strip_meta(&mut value);
+        assert_eq!(value, json!([1, 2]));
+    }
+
+    #[test]
+    fn test_without_meta() {
+        let value = json!({ "_id": "11111111111111111111", "name": "kuy" });
+        assert_eq!(without_meta(&value), json!({ "name": "kuy" }));
+        assert_eq!(value["_id"], "11111111111111111111");
+    }
+}
@@ -0,0 +1,267 @@
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::client::{Client, Meta};
+use crate::error::{Error, Result};
+use crate::QueryBuilder;
+
+/// A change detected by `Watch`.
+#[derive(Debug)]
+pub enum Event {
+    Created(Value, Meta),
+    Updated(Value, Meta),
+    /// A record which was matching the query is gone, by id.
+    Deleted(String),
+}
+
+/// Changes of records matching a query, detected by polling. Use `Client::watch()` to get it.
+///
+/// Every poll reads records sorted by `-_updatedOn`, then by `-_createdOn` for records never
+/// updated, until they fall below the high-water mark, the latest `_updatedOn` seen, and emits
+/// `Created` and `Updated` events (oldest first).
+/// The first poll only sets the mark without emitting anything.
+///
+/// Deletions can't be seen that way, so unless disabled with `deletions(false)`, every poll also
+/// scans all matching records sorted by `_createdOn`, which updates don't reorder, and emits
+/// `Deleted` for records gone since the previous scan. A record missing from the scan is read
+/// by id before being reported, in case a concurrent deletion shifted it to a page already read.
+/// A record leaving the query because of an update is reported as deleted.
+///
+/// As an iterator, `Watch` never ends: it polls every `interval` until it finds changes.
+/// Errors are yielded as they occur and the next call polls again.
+pub struct Watch<'a> {
+    query: QueryBuilder<'a>,
+    interval: Duration,
+    deletions: bool,
+    started: bool,
+    seen: HashMap<String, String>,
+    high_water: Option<String>,
+    boundary: HashSet<String>,
+    events: VecDeque<Event>,
+    polled: Option<Instant>,
+}
+
+impl<'a> Watch<'a> {
+    /// Detect deleted records, which needs a scan of all matching records on every poll.
+    /// Enabled by default.
+    pub fn deletions(mut self, detect: bool) -> Watch<'a> {
+        self.deletions = detect;
+        self
+    }
+
+    /// Poll once, returning changes since the previous poll.
+    pub fn poll(&mut self) -> Result<Vec<Event>> {
+        self.polled = Some(Instant::now());
+
+        let mut changed = self.changes()?;
+        let current = if self.deletions {
+            Some(self.scan_ids()?)
+        } else {
+            None
+        };
+        let previous = self.high_water.clone();
+        let boundary = &self.boundary;
+        changed.retain(|(_, meta)| {
+            !(boundary.contains(&meta.id) && previous.as_ref() == Some(&meta.updated_on))
+        });
+        let deleted = match current {
+            Some(mut current) if self.started => {
+                for (_, meta) in &changed {
+                    current.insert(meta.id.clone(), meta.updated_on.clone());
+                }
+                let deleted = self.confirm_deleted(&mut current)?;
+                self.seen = current;
+                deleted
+            }
+            Some(current) => {
+                self.seen = current;
+                vec![]
+            }
+            None => vec![],
+        };
+
+        // Ids at the high-water mark, which the next poll reads again.
+        let boundary = mem::take(&mut self.boundary);
+        let latest = changed.iter().map(|(_, m)| &m.updated_on).max().cloned();
+        if latest > previous {
+            self.high_water = latest;
+        } else {
+            self.boundary = boundary.clone();
+        }
+        let high_water = self.high_water.as_ref();
+        self.boundary.extend(
+            changed
+                .iter()
+                .filter(|(_, meta)| Some(&meta.updated_on) == high_water)
+                .map(|(_, meta)| meta.id.clone()),
+        );
+
+        if !self.started {
+            self.started = true;
+            return Ok(vec![]);
+        }
+
+        changed.sort_by(|(_, a), (_, b)| a.updated_on.cmp(&b.updated_on));
+        let events = changed
+            .into_iter()
+            .map(|(record, meta)| {
+                let created = match &previous {
+                    Some(previous) => {
+                        meta.created_on > *previous
+                            || (meta.created_on == *previous && !boundary.contains(&meta.id))
+                    }
+                    None => true,
+                };
+                if created {
+                    Event::Created(record, meta)
+                } else {
+                    Event::Updated(record, meta)
+                }
+            })
+            .chain(deleted.into_iter().map(Event::Deleted))
+            .collect();
+        Ok(events)
+    }
+
+    /// The latest `_updatedOn` seen so far.
+    pub fn high_water_mark(&self) -> Option<&str> {
+        self.high_water.as_deref()
+    }
+
+    /// Records updated or created at or after the high-water mark. jsonbox only sets `_updatedOn`
+    /// on update, so records never updated sort last by `-_updatedOn` and are found by a second
+    /// scan by `-_createdOn`. Before the first poll, the mark is taken from the latest record of
+    /// both scans, so only records at the mark are returned.
+    fn changes(&self) -> Result<Vec<(Value, Meta)>> {
+        let mut threshold = if self.started {
+            self.high_water.clone()
+        } else {
+            None
+        };
+        let mut ids = HashSet::new();
+        let mut changed = vec![];
+        for field in ["_updatedOn", "_createdOn"].iter() {
+            let mut query = self.query.clone();
+            query.order_by(field).desc();
+            'pages: for page in query.pages() {
+                for (record, meta) in page? {
+                    let value = if *field == "_updatedOn" {
+                        &meta.updated_on
+                    } else {
+                        &meta.created_on
+                    };
+                    if !self.started && threshold.as_ref() < Some(value) {
+                        threshold = Some(value.clone());
+                    }
+                    if threshold.as_ref() > Some(value) {
+                        break 'pages;
+                    }
+                    // A record updated during the scan moves to the first page and may be read twice.
+                    if ids.insert(meta.id.clone()) {
+                        changed.push((record, meta));
+                    }
+                }
+            }
+        }
+        // The mark of the first poll may have been raised by the second scan.
+        changed.retain(|(_, meta)| threshold.as_ref() <= Some(&meta.updated_on));
+        Ok(changed)
+    }
+
+    /// `_updatedOn` of all matching records by id, scanned in creation order.
+    fn scan_ids(&self) -> Result<HashMap<String, String>> {
+        let mut query = self.query.clone();
+        query.order_by("_createdOn");
+        let mut current = HashMap::new();
+        for page in query.pages() {
+            for (_, meta) in page? {
+                current.insert(meta.id, meta.updated_on);
+            }
+        }
+        Ok(current)
+    }
+
+    /// Ids seen by the previous scan which are really gone. Records missed by `current` but
+    /// found unchanged are added back to it.
+    fn confirm_deleted(&self, current: &mut HashMap<String, String>) -> Result<Vec<String>> {
+        let mut missing: Vec<(&String, &String)> = self
+            .seen
+            .iter()
+            .filter(|(id, _)| !current.contains_key(*id))
+            .collect();
+        missing.sort();
+
+        let mut deleted = vec![];
+        for (id, updated_on) in missing {
+            match self.query.client.fetch_by_id::<Value>(id) {
+                Ok((_, meta)) if meta.updated_on == *updated_on => {
+                    current.insert(meta.id, meta.updated_on);
+                }
+                // Updated so that it doesn't match the query anymore.
+                Ok(_) => deleted.push(id.clone()),
+                Err(Error::General { code, .. }) if code < 500 => deleted.push(id.clone()),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(deleted)
+    }
+}
+
+impl<'a> Iterator for Watch<'a> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(Ok(event));
+            }
+            if let Some(polled) = self.polled {
+                let elapsed = polled.elapsed();
+                if elapsed < self.interval {
+                    thread::sleep(self.interval - elapsed);
+                }
+            }
+            match self.poll() {
+                Ok(events) => self.events.extend(events),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+impl<'a> Client<'a> {
+    /// Watch changes of records matching `query` by polling every `interval`.
+    ///
+    /// Filters of `query` are kept, its sort order is replaced, and its limit is used as the
+    /// page size. The cache is bypassed.
+    ///
+    /// ```ignore
+    /// for event in client.watch(client.read().filter_by("done:{}", false), Duration::from_secs(5)) {
+    ///     match event? {
+    ///         Event::Created(record, meta) => println!("created {}: {}", meta.id, record),
+    ///         Event::Updated(record, meta) => println!("updated {}: {}", meta.id, record),
+    ///         Event::Deleted(id) => println!("deleted {}", id),
+    ///     }
+    /// }
+    /// ```
+    pub fn watch<'q>(&'q self, query: &QueryBuilder<'q>, interval: Duration) -> Watch<'q> {
+        let mut query = query.clone();
+        query.client = self;
+        query.order_by("_updatedOn").desc().skip(0);
+        query.fresh = true;
+        Watch {
+            query,
+            interval,
+            deletions: true,
+            started: false,
+            seen: HashMap::new(),
+            high_water: None,
+            boundary: HashSet::new(),
+            events: VecDeque::new(),
+            polled: None,
+        }
+    }
+}
@@ -14,6 +14,68 @@ pub enum Error {
 
     #[snafu(display("General: [{}] {}", "code", "message"))]
     General { code: u16, message: String },
+
+    #[snafu(display("IO: {}", source))]
+    Io { source: std::io::Error },
+
+    #[snafu(display("Cassette: {}", message))]
+    Cassette { message: String },
+
+    #[snafu(display("Patch: test failed at '{}'", path))]
+    PatchTest {
+        path: String,
+        expected: serde_json::Value,
+        actual: Option<serde_json::Value>,
+    },
+
+    #[snafu(display("Patch: invalid operation at '{}': {}", path, message))]
+    InvalidPatch { path: String, message: String },
+
+    #[snafu(display("Conflict: record '{}' was modified at {}", id, actual))]
+    Conflict {
+        id: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[snafu(display("Duplicate: {} records have {}={}", ids.len(), field, value))]
+    Duplicate {
+        field: String,
+        value: String,
+        ids: Vec<String>,
+    },
+
+    #[snafu(display("Interrupted after {:?}: {}", checkpoint, source))]
+    Interrupted {
+        checkpoint: Option<String>,
+        source: Box<Error>,
+    },
+
+    #[snafu(display("Checkpoint '{}' not found, nothing was resumed", checkpoint))]
+    CheckpointNotFound { checkpoint: String },
+
+    #[snafu(display("Schema: unsupported version {} (latest is {})", found, latest))]
+    UnsupportedVersion { found: String, latest: u64 },
+
+    #[snafu(display(
+        "Validation: {}",
+        violations.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+    ))]
+    Validation {
+        violations: Vec<crate::client::validate::Violation>,
+    },
+
+    #[snafu(display("Payload: {} bytes exceeds the limit of {} bytes", size, limit))]
+    PayloadTooLarge { size: usize, limit: usize },
+
+    #[snafu(display("Payload: reserved keys {}", paths.join(", ")))]
+    ReservedKey { paths: Vec<String> },
+
+    #[snafu(display("Count mismatch: expected {} records, found {}", expected, actual))]
+    CountMismatch { expected: usize, actual: usize },
+
+    #[snafu(display("Cursor: {}", message))]
+    InvalidCursor { message: String },
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
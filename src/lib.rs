@@ -113,8 +113,32 @@ extern crate matches;
 
 mod client;
 mod error;
+mod patch;
+mod trace;
 mod url;
 
+pub use crate::client::bulk::{BulkReport, ChunkOptions, ChunkResult};
+pub use crate::client::cache::CacheStats;
+pub use crate::client::cassette::Cassette;
+pub use crate::client::copy::{CopyOptions, CopyReport};
+pub use crate::client::cursor::{Cursor, Page};
+pub use crate::client::diff::{diff, BoxDiff, ChangedRecord, DiffRecord};
+pub use crate::client::metrics::{Histogram, MetricsSnapshot, OperationMetrics};
+pub use crate::client::middleware::{Middleware, Next, Request, Response};
+pub use crate::client::migrate::{MigrationOptions, MigrationReport};
+pub use crate::client::offline::FlushReport;
 pub use crate::client::query_builder::QueryBuilder;
-pub use crate::client::Client;
+pub use crate::client::raw::RawRecord;
+pub use crate::client::schema::Versioned;
+pub use crate::client::stream::RecordStream;
+pub use crate::client::sync::{MatchBy, Resolution, Side, SyncAction, SyncOptions, SyncPlan};
+pub use crate::client::upsert::Upsert;
+#[cfg(feature = "json-schema")]
+pub use crate::client::validate::JsonSchema;
+pub use crate::client::validate::{Validator, Violation};
+pub use crate::client::value::strip_meta;
+pub use crate::client::watch::{Event, Watch};
+pub use crate::client::{Client, Meta};
 pub use crate::error::{Error, Result};
+pub use crate::patch::{apply_patch, json_patch_diff, merge_patch, PatchOperation};
+pub use reqwest::{header, Method};
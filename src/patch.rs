@@ -0,0 +1,496 @@
+//! Helpers to modify JSON documents.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use snafu::ResultExt;
+
+use crate::error::{self, Error, Result};
+
+/// Apply a JSON Merge Patch ([RFC 7396](https://tools.ietf.org/html/rfc7396)) to `target`.
+///
+/// Members of `patch` set to `null` are removed from `target`, objects are merged recursively
+/// and any other value replaces the one in `target`.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            if let Value::Object(target) = target {
+                for (key, value) in patch {
+                    if value.is_null() {
+                        target.remove(key);
+                    } else {
+                        merge_patch(target.entry(key.as_str()).or_insert(Value::Null), value);
+                    }
+                }
+            }
+        }
+        _ => *target = patch.clone(),
+    }
+}
+
+/// An operation of JSON Patch ([RFC 6902](https://tools.ietf.org/html/rfc6902)).
+///
+/// Serialized in the standard form, e.g. `{"op":"replace","path":"/name","value":"kuy"}`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// Apply JSON Patch operations to `target`.
+///
+/// Operations are applied in order. If any of them fails, `target` is left untouched and
+/// `Error::PatchTest` (for a failed `test` operation) or `Error::InvalidPatch` is returned.
+pub fn apply_patch(target: &mut Value, ops: &[PatchOperation]) -> Result<()> {
+    let mut doc = target.clone();
+    for op in ops {
+        match op {
+            PatchOperation::Add { path, value } => add(&mut doc, path, value.clone())?,
+            PatchOperation::Remove { path } => {
+                remove(&mut doc, path)?;
+            }
+            PatchOperation::Replace { path, value } => {
+                *pointer_mut(&mut doc, path)? = value.clone();
+            }
+            PatchOperation::Move { from, path } => {
+                if path.starts_with(&format!("{}/", from)) {
+                    return Err(invalid(
+                        path,
+                        "cannot move a value into one of its children",
+                    ));
+                }
+                let value = remove(&mut doc, from)?;
+                add(&mut doc, path, value)?;
+            }
+            PatchOperation::Copy { from, path } => {
+                let value = pointer(&doc, from)?.clone();
+                add(&mut doc, path, value)?;
+            }
+            PatchOperation::Test { path, value } => {
+                let actual = pointer(&doc, path).ok();
+                if actual != Some(value) {
+                    return Err(Error::PatchTest {
+                        path: path.clone(),
+                        expected: value.clone(),
+                        actual: actual.cloned(),
+                    });
+                }
+            }
+        }
+    }
+    *target = doc;
+    Ok(())
+}
+
+/// Compute JSON Patch operations transforming `from` into `to`.
+pub fn json_patch_diff<T>(from: &T, to: &T) -> Result<Vec<PatchOperation>>
+where
+    T: Serialize,
+{
+    let from = serde_json::to_value(from).context(error::Json { reason: "diff" })?;
+    let to = serde_json::to_value(to).context(error::Json { reason: "diff" })?;
+    let mut ops = vec![];
+    diff_values(&mut ops, "", &from, &to);
+    Ok(ops)
+}
+
+fn diff_values(ops: &mut Vec<PatchOperation>, path: &str, from: &Value, to: &Value) {
+    if from == to {
+        return;
+    }
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            for (key, value) in from {
+                let path = format!("{}/{}", path, escape(key));
+                match to.get(key) {
+                    Some(other) => diff_values(ops, &path, value, other),
+                    None => ops.push(PatchOperation::Remove { path }),
+                }
+            }
+            for (key, value) in to {
+                if !from.contains_key(key) {
+                    ops.push(PatchOperation::Add {
+                        path: format!("{}/{}", path, escape(key)),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(from), Value::Array(to)) => {
+            let common = from.len().min(to.len());
+            for i in 0..common {
+                diff_values(ops, &format!("{}/{}", path, i), &from[i], &to[i]);
+            }
+            for i in (common..from.len()).rev() {
+                ops.push(PatchOperation::Remove {
+                    path: format!("{}/{}", path, i),
+                });
+            }
+            for value in &to[common..] {
+                ops.push(PatchOperation::Add {
+                    path: format!("{}/-", path),
+                    value: value.clone(),
+                });
+            }
+        }
+        _ => ops.push(PatchOperation::Replace {
+            path: path.to_string(),
+            value: to.clone(),
+        }),
+    }
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn invalid(path: &str, message: &str) -> Error {
+    Error::InvalidPatch {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// Split a JSON Pointer ([RFC 6901](https://tools.ietf.org/html/rfc6901)) into unescaped tokens.
+fn tokens(path: &str) -> Result<Vec<String>> {
+    if path.is_empty() {
+        return Ok(vec![]);
+    }
+    if !path.starts_with('/') {
+        return Err(invalid(path, "pointer must start with '/'"));
+    }
+    Ok(path[1..]
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn index(path: &str, token: &str, len: usize) -> Result<usize> {
+    let valid = !token.is_empty()
+        && token.chars().all(|c| c.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    match token.parse::<usize>() {
+        Ok(i) if valid && i < len => Ok(i),
+        _ => Err(invalid(path, "array index out of bounds")),
+    }
+}
+
+fn child<'v>(path: &str, value: &'v Value, token: &str) -> Result<&'v Value> {
+    match value {
+        Value::Object(map) => map
+            .get(token)
+            .ok_or_else(|| invalid(path, "no such member")),
+        Value::Array(list) => Ok(&list[index(path, token, list.len())?]),
+        _ => Err(invalid(path, "parent is not a container")),
+    }
+}
+
+fn child_mut<'v>(path: &str, value: &'v mut Value, token: &str) -> Result<&'v mut Value> {
+    match value {
+        Value::Object(map) => map
+            .get_mut(token)
+            .ok_or_else(|| invalid(path, "no such member")),
+        Value::Array(list) => {
+            let i = index(path, token, list.len())?;
+            Ok(&mut list[i])
+        }
+        _ => Err(invalid(path, "parent is not a container")),
+    }
+}
+
+fn pointer<'v>(doc: &'v Value, path: &str) -> Result<&'v Value> {
+    tokens(path)?
+        .iter()
+        .try_fold(doc, |value, token| child(path, value, token))
+}
+
+fn pointer_mut<'v>(doc: &'v mut Value, path: &str) -> Result<&'v mut Value> {
+    tokens(path)?
+        .iter()
+        .try_fold(doc, |value, token| child_mut(path, value, token))
+}
+
+/// Resolve the parent of the value at `path` and the last token.
+fn parent_mut<'v>(doc: &'v mut Value, path: &str) -> Result<(&'v mut Value, String)> {
+    let mut tokens = tokens(path)?;
+    let last = tokens
+        .pop()
+        .ok_or_else(|| invalid(path, "operation not allowed on the root"))?;
+    let parent = tokens
+        .iter()
+        .try_fold(doc, |value, token| child_mut(path, value, token))?;
+    Ok((parent, last))
+}
+
+fn add(doc: &mut Value, path: &str, value: Value) -> Result<()> {
+    if path.is_empty() {
+        *doc = value;
+        return Ok(());
+    }
+    let (parent, last) = parent_mut(doc, path)?;
+    match parent {
+        Value::Object(map) => {
+            map.insert(last, value);
+        }
+        Value::Array(list) => {
+            let i = if last == "-" {
+                list.len()
+            } else {
+                index(path, &last, list.len() + 1)?
+            };
+            list.insert(i, value);
+        }
+        _ => return Err(invalid(path, "parent is not a container")),
+    }
+    Ok(())
+}
+
+fn remove(doc: &mut Value, path: &str) -> Result<Value> {
+    let (parent, last) = parent_mut(doc, path)?;
+    match parent {
+        Value::Object(map) => map
+            .remove(&last)
+            .ok_or_else(|| invalid(path, "no such member")),
+        Value::Array(list) => {
+            let i = index(path, &last, list.len())?;
+            Ok(list.remove(i))
+        }
+        _ => Err(invalid(path, "parent is not a container")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matches::assert_matches;
+    use serde_json::json;
+
+    fn patched(target: Value, ops: Value) -> Result<Value> {
+        let mut target = target;
+        let ops: Vec<PatchOperation> = serde_json::from_value(ops).unwrap();
+        apply_patch(&mut target, &ops)?;
+        Ok(target)
+    }
+
+    fn merged(target: Value, patch: Value) -> Value {
+        let mut target = target;
+        merge_patch(&mut target, &patch);
+        target
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        // Test cases from RFC 7396, Appendix A.
+        assert_eq!(merged(json!({"a":"b"}), json!({"a":"c"})), json!({"a":"c"}));
+        assert_eq!(
+            merged(json!({"a":"b"}), json!({"b":"c"})),
+            json!({"a":"b","b":"c"})
+        );
+        assert_eq!(merged(json!({"a":"b"}), json!({"a":null})), json!({}));
+        assert_eq!(
+            merged(json!({"a":"b","b":"c"}), json!({"a":null})),
+            json!({"b":"c"})
+        );
+        assert_eq!(
+            merged(json!({"a":["b"]}), json!({"a":"c"})),
+            json!({"a":"c"})
+        );
+        assert_eq!(
+            merged(json!({"a":"c"}), json!({"a":["b"]})),
+            json!({"a":["b"]})
+        );
+        assert_eq!(
+            merged(json!({"a":{"b":"c"}}), json!({"a":{"b":"d","c":null}})),
+            json!({"a":{"b":"d"}})
+        );
+        assert_eq!(
+            merged(json!({"a":[{"b":"c"}]}), json!({"a":[1]})),
+            json!({"a":[1]})
+        );
+        assert_eq!(
+            merged(json!(["a", "b"]), json!(["c", "d"])),
+            json!(["c", "d"])
+        );
+        assert_eq!(merged(json!({"a":"b"}), json!(["c"])), json!(["c"]));
+        assert_eq!(merged(json!({"a":"foo"}), json!(null)), json!(null));
+        assert_eq!(merged(json!({"a":"foo"}), json!("bar")), json!("bar"));
+        assert_eq!(
+            merged(json!({"e":null}), json!({"a":1})),
+            json!({"e":null,"a":1})
+        );
+        assert_eq!(
+            merged(json!([1, 2]), json!({"a":"b","c":null})),
+            json!({"a":"b"})
+        );
+        assert_eq!(
+            merged(json!({}), json!({"a":{"bb":{"ccc":null}}})),
+            json!({"a":{"bb":{}}})
+        );
+    }
+
+    #[test]
+    fn test_apply_patch() {
+        // Test cases from RFC 6902, Appendix A.
+        assert_eq!(
+            patched(
+                json!({"foo":"bar"}),
+                json!([{"op":"add","path":"/baz","value":"qux"}])
+            )
+            .unwrap(),
+            json!({"baz":"qux","foo":"bar"})
+        );
+        assert_eq!(
+            patched(
+                json!({"foo":["bar","baz"]}),
+                json!([{"op":"add","path":"/foo/1","value":"qux"}])
+            )
+            .unwrap(),
+            json!({"foo":["bar","qux","baz"]})
+        );
+        assert_eq!(
+            patched(
+                json!({"baz":"qux","foo":"bar"}),
+                json!([{"op":"remove","path":"/baz"}])
+            )
+            .unwrap(),
+            json!({"foo":"bar"})
+        );
+        assert_eq!(
+            patched(
+                json!({"foo":["bar","qux","baz"]}),
+                json!([{"op":"remove","path":"/foo/1"}])
+            )
+            .unwrap(),
+            json!({"foo":["bar","baz"]})
+        );
+        assert_eq!(
+            patched(
+                json!({"baz":"qux","foo":"bar"}),
+                json!([{"op":"replace","path":"/baz","value":"boo"}])
+            )
+            .unwrap(),
+            json!({"baz":"boo","foo":"bar"})
+        );
+        assert_eq!(
+            patched(
+                json!({"foo":{"bar":"baz","waldo":"fred"},"qux":{"corge":"grault"}}),
+                json!([{"op":"move","from":"/foo/waldo","path":"/qux/thud"}])
+            )
+            .unwrap(),
+            json!({"foo":{"bar":"baz"},"qux":{"corge":"grault","thud":"fred"}})
+        );
+        assert_eq!(
+            patched(
+                json!({"foo":["all","grass","cows","eat"]}),
+                json!([{"op":"move","from":"/foo/1","path":"/foo/3"}])
+            )
+            .unwrap(),
+            json!({"foo":["all","cows","eat","grass"]})
+        );
+        assert_eq!(
+            patched(
+                json!({"baz":"qux","foo":["a",2,"c"]}),
+                json!([
+                    {"op":"test","path":"/baz","value":"qux"},
+                    {"op":"test","path":"/foo/1","value":2}
+                ])
+            )
+            .unwrap(),
+            json!({"baz":"qux","foo":["a",2,"c"]})
+        );
+        assert_eq!(
+            patched(
+                json!({"foo":["bar"]}),
+                json!([{"op":"add","path":"/foo/-","value":["abc","def"]}])
+            )
+            .unwrap(),
+            json!({"foo":["bar",["abc","def"]]})
+        );
+        assert_eq!(
+            patched(
+                json!({"/":9,"~1":10}),
+                json!([{"op":"test","path":"/~01","value":10}])
+            )
+            .unwrap(),
+            json!({"/":9,"~1":10})
+        );
+        assert_eq!(
+            patched(
+                json!({"foo":"bar"}),
+                json!([{"op":"copy","from":"/foo","path":"/baz"}])
+            )
+            .unwrap(),
+            json!({"foo":"bar","baz":"bar"})
+        );
+    }
+
+    #[test]
+    fn test_apply_patch_errors() {
+        let res = patched(
+            json!({"baz":"qux"}),
+            json!([{"op":"test","path":"/baz","value":"bar"}]),
+        );
+        assert_matches!(res, Err(Error::PatchTest { ref path, .. }) if path == "/baz");
+
+        let res = patched(
+            json!({"foo":"bar"}),
+            json!([{"op":"add","path":"/baz/bat","value":"qux"}]),
+        );
+        assert_matches!(res, Err(Error::InvalidPatch { .. }));
+
+        let res = patched(
+            json!({"foo":["bar"]}),
+            json!([{"op":"remove","path":"/foo/1"}]),
+        );
+        assert_matches!(res, Err(Error::InvalidPatch { .. }));
+
+        let res = patched(
+            json!({"foo":{"bar":1}}),
+            json!([{"op":"move","from":"/foo","path":"/foo/bar/baz"}]),
+        );
+        assert_matches!(res, Err(Error::InvalidPatch { .. }));
+
+        // Failed patch leaves the target untouched.
+        let mut target = json!({"foo":"bar"});
+        let ops = vec![
+            PatchOperation::Remove {
+                path: "/foo".into(),
+            },
+            PatchOperation::Remove {
+                path: "/foo".into(),
+            },
+        ];
+        assert!(apply_patch(&mut target, &ops).is_err());
+        assert_eq!(target, json!({"foo":"bar"}));
+    }
+
+    #[test]
+    fn test_json_patch_diff() {
+        let from = json!({"name":"kuy","tags":["a","b","c"],"profile":{"city":"Tokyo","a/b":1}});
+        let to = json!({"name":"kuy","tags":["a","x"],"profile":{"city":"Osaka"},"count":1});
+        let ops = json_patch_diff(&from, &to).unwrap();
+        assert_eq!(
+            serde_json::to_value(&ops).unwrap(),
+            json!([
+                {"op":"remove","path":"/profile/a~1b"},
+                {"op":"replace","path":"/profile/city","value":"Osaka"},
+                {"op":"replace","path":"/tags/1","value":"x"},
+                {"op":"remove","path":"/tags/2"},
+                {"op":"add","path":"/count","value":1}
+            ])
+        );
+
+        let mut patched = from.clone();
+        apply_patch(&mut patched, &ops).unwrap();
+        assert_eq!(patched, to);
+
+        assert!(json_patch_diff(&from, &from).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+//! Instrumentation of requests issued by `Client`.
+//!
+//! With the `tracing` feature enabled, every operation runs inside a `jsonbox` span carrying
+//! the operation, HTTP method, box id, record id and query string. Status, latency and response
+//! size are recorded on the span when the response arrives. Headers are never recorded, so API
+//! keys injected into requests don't end up in logs. Without the feature all of this compiles
+//! down to nothing.
+
+use std::time::Duration;
+
+use crate::client::Operation;
+use crate::error::Error;
+
+/// Guard keeping the span of an operation entered until it's dropped.
+pub(crate) struct Span {
+    #[cfg(feature = "tracing")]
+    _entered: tracing::span::EnteredSpan,
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn span(
+    op: Operation,
+    box_id: &str,
+    record_id: Option<&str>,
+    query: Option<&str>,
+) -> Span {
+    let span = tracing::info_span!(
+        "jsonbox",
+        op = op.as_str(),
+        method = op.method().as_str(),
+        box_id = box_id,
+        record_id = record_id.unwrap_or(""),
+        query = query.unwrap_or(""),
+        status = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+        response_size = tracing::field::Empty,
+    );
+    Span {
+        _entered: span.entered(),
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn span(_: Operation, _: &str, _: Option<&str>, _: Option<&str>) -> Span {
+    Span {}
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn completed(status: u16, size: usize, latency: Duration) {
+    let span = tracing::Span::current();
+    span.record("status", status);
+    span.record("latency_ms", latency.as_millis() as u64);
+    span.record("response_size", size as u64);
+    if status < 400 {
+        tracing::debug!("request completed");
+    } else {
+        tracing::warn!("request failed");
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn completed(_: u16, _: usize, _: Duration) {}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn failed(err: &Error, latency: Duration) {
+    tracing::Span::current().record("latency_ms", latency.as_millis() as u64);
+    tracing::warn!(error = %err, "request failed");
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn failed(_: &Error, _: Duration) {}
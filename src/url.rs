@@ -1,4 +1,4 @@
-pub const BASE_URL: &'static str = "https://jsonbox.io";
+pub const BASE_URL: &str = "https://jsonbox.io";
 
 pub fn of_box(base_url: &str, box_id: &str) -> String {
     format!("{}/{}", base_url, box_id)
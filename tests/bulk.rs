@@ -0,0 +1,137 @@
+use jsonbox::{ChunkOptions, Client, Error};
+use matches::*;
+use mockito::{mock, Matcher};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Data {
+    name: String,
+    count: i32,
+}
+
+fn data(count: i32) -> Data {
+    Data {
+        name: format!("n{}", count),
+        count,
+    }
+}
+
+fn created(id: u32, count: i32) -> String {
+    format!(
+        r#"{{"_id":"{:020}","name":"n{}","count":{},"_createdOn":"2019-09-22T12:24:37.513Z"}}"#,
+        id, count, count
+    )
+}
+
+#[test]
+fn test_create_bulk_chunked() {
+    let _m1 = mock("POST", "/b0000000000000000000")
+        .match_body(Matcher::Json(json!([
+            {"name":"n1","count":1},
+            {"name":"n2","count":2}
+        ])))
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(format!("[{},{}]", created(1, 1), created(2, 2)))
+        .create();
+    let _m2 = mock("POST", "/b0000000000000000000")
+        .match_body(Matcher::Json(json!([
+            {"name":"n3","count":3},
+            {"name":"n4","count":4}
+        ])))
+        .with_status(400)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Too many requests"}"#)
+        .create();
+    let _m3 = mock("POST", "/b0000000000000000000")
+        .match_body(Matcher::Json(json!([{"name":"n5","count":5}])))
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(format!("[{}]", created(5, 5)))
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("b0000000000000000000").with_base_url(&server_url);
+
+    let records: Vec<Data> = (1..=5).map(data).collect();
+    let options = ChunkOptions::new().max_records(2).concurrency(3);
+    let chunks = client.create_bulk_chunked(&records, &options);
+    let chunks = chunks.unwrap();
+    assert_eq!(chunks.len(), 3);
+
+    assert_eq!(chunks[0].range, 0..2);
+    let created = chunks[0].result.as_ref().unwrap();
+    assert_eq!(created[1].0.name, "n2");
+    assert_eq!(created[1].1.id, "00000000000000000002");
+
+    assert_eq!(chunks[1].range, 2..4);
+    assert_matches!(chunks[1].result, Err(Error::General { code, .. }) if code == 400);
+
+    assert_eq!(chunks[2].range, 4..5);
+    assert_eq!(
+        chunks[2].result.as_ref().unwrap()[0].1.id,
+        "00000000000000000005"
+    );
+}
+
+#[test]
+fn test_update_many() {
+    let _m1 = mock("PUT", "/b1111111111111111111/11111111111111111111")
+        .match_body(Matcher::Json(json!({"name":"n1","count":1})))
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Record updated."}"#)
+        .create();
+    let _m2 = mock("PUT", "/b1111111111111111111/22222222222222222222")
+        .with_status(400)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Invalid record Id"}"#)
+        .create();
+    let _m3 = mock("PUT", "/b1111111111111111111/33333333333333333333")
+        .match_body(Matcher::Json(json!({"name":"n3","count":3})))
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Record updated."}"#)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("b1111111111111111111").with_base_url(&server_url);
+
+    let items = vec![
+        ("11111111111111111111", data(1)),
+        ("22222222222222222222", data(2)),
+        ("33333333333333333333", data(3)),
+    ];
+    let report = client.update_many(items, 2);
+    assert!(!report.is_ok());
+    assert_eq!(
+        report.succeeded,
+        vec!["11111111111111111111", "33333333333333333333"]
+    );
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, "22222222222222222222");
+    assert_matches!(report.failed[0].1, Error::General { code, .. } if code == 400);
+}
+
+#[test]
+fn test_delete_many() {
+    let _m1 = mock("DELETE", "/b2222222222222222222/11111111111111111111")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Record removed."}"#)
+        .create();
+    let _m2 = mock("DELETE", "/b2222222222222222222/22222222222222222222")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Record removed."}"#)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("b2222222222222222222").with_base_url(&server_url);
+
+    let ids = vec![
+        "11111111111111111111".to_string(),
+        "22222222222222222222".to_string(),
+    ];
+    let report = client.delete_many(ids, 1);
+    assert!(report.is_ok());
+    assert_eq!(report.succeeded.len(), 2);
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::Script;
+use jsonbox::header::HeaderMap;
+use jsonbox::{CacheStats, Client, Method, Next, Request, Response};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Data {
+    name: String,
+}
+
+const RECORD: &str =
+    r#"{"_id":"11111111111111111111","name":"kuy","_createdOn":"2019-09-22T12:24:37.513Z"}"#;
+const RECORDS: &str =
+    r#"[{"_id":"11111111111111111111","name":"kuy","_createdOn":"2019-09-22T12:24:37.513Z"}]"#;
+
+#[test]
+fn test_read_through() {
+    let script = Script::new();
+    script.push(200, RECORD).push(200, RECORDS);
+    let client = Client::new("k0000000000000000000")
+        .with_middleware(script.clone())
+        .with_cache(16, Duration::from_secs(60));
+
+    for _ in 0..3 {
+        let (data, _) = client.read().id::<Data>("11111111111111111111").unwrap();
+        assert_eq!(data.name, "kuy");
+        let records = client.read().limit(1).run::<Data>().unwrap();
+        assert_eq!(records.len(), 1);
+    }
+
+    assert_eq!(script.log().len(), 2);
+    assert_eq!(
+        client.cache_stats(),
+        Some(CacheStats {
+            hits: 4,
+            misses: 2,
+            evictions: 0,
+            entries: 2,
+        })
+    );
+    assert_eq!(client.metrics().operation("read_by_id").unwrap().count, 1);
+}
+
+#[test]
+fn test_invalidate_on_write() {
+    let script = Script::new();
+    script
+        .push(200, RECORD)
+        .push(200, RECORDS)
+        .push(200, r#"{"message":"Record updated."}"#)
+        .push(200, RECORD)
+        .push(200, RECORDS);
+    let client = Client::new("k1111111111111111111")
+        .with_middleware(script.clone())
+        .with_cache(16, Duration::from_secs(60));
+
+    client.read().id::<Data>("11111111111111111111").unwrap();
+    client.read().limit(1).run::<Data>().unwrap();
+    let data = Data {
+        name: "Yuki".into(),
+    };
+    client.update("11111111111111111111", &data).unwrap();
+    client.read().id::<Data>("11111111111111111111").unwrap();
+    client.read().limit(1).run::<Data>().unwrap();
+
+    assert_eq!(script.log().len(), 5);
+    assert_eq!(script.remaining(), 0);
+}
+
+#[test]
+fn test_skip_put_after_write_in_flight() {
+    let script = Script::new();
+    script
+        .push(200, r#"{"message":"Record updated."}"#)
+        .push(200, RECORD);
+    // Hold the first read until the update is done, then answer with the old record.
+    let (sent, written) = (Arc::new(Barrier::new(2)), Arc::new(Barrier::new(2)));
+    let (in_flight, done) = (sent.clone(), written.clone());
+    let held = Arc::new(AtomicBool::new(false));
+    let client = Client::new("k3333333333333333333")
+        .with_middleware(move |req: Request, next: Next| {
+            if req.method == Method::GET && !held.swap(true, Ordering::SeqCst) {
+                in_flight.wait();
+                done.wait();
+                return Ok(Response {
+                    status: 200,
+                    headers: HeaderMap::new(),
+                    body: RECORD.into(),
+                });
+            }
+            next.run(req)
+        })
+        .with_middleware(script.clone())
+        .with_cache(16, Duration::from_secs(60));
+
+    thread::scope(|scope| {
+        let read = scope.spawn(|| client.read().id::<Data>("11111111111111111111"));
+        sent.wait();
+        let data = Data {
+            name: "Yuki".into(),
+        };
+        client.update("11111111111111111111", &data).unwrap();
+        written.wait();
+        read.join().unwrap().unwrap();
+    });
+
+    // The response of the read predates the update, so it wasn't cached.
+    client.read().id::<Data>("11111111111111111111").unwrap();
+    assert_eq!(script.log().len(), 2);
+    assert_eq!(client.cache_stats().unwrap().entries, 1);
+}
+
+#[test]
+fn test_disabled_by_default() {
+    let script = Script::new();
+    script.push(200, RECORD).push(200, RECORD);
+    let client = Client::new("k2222222222222222222").with_middleware(script.clone());
+
+    client.read().id::<Data>("11111111111111111111").unwrap();
+    client.read().id::<Data>("11111111111111111111").unwrap();
+    assert_eq!(script.log().len(), 2);
+    assert_eq!(client.cache_stats(), None);
+}
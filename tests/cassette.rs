@@ -0,0 +1,78 @@
+use jsonbox::{Cassette, Client, Error};
+use matches::*;
+use mockito::mock;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Data {
+    name: String,
+    count: i32,
+}
+
+#[test]
+fn test_replay() {
+    let cassette = Cassette::replay("tests/cassettes/crud.json").unwrap();
+    assert_eq!(cassette.len(), 5);
+
+    // Nothing listens on this address, every response comes from the cassette.
+    let client = Client::new("c0000000000000000000")
+        .with_base_url("http://127.0.0.1:9")
+        .with_middleware(cassette);
+
+    let data = Data {
+        name: "rust".into(),
+        count: 42,
+    };
+    let (_, meta) = client.create(&data).unwrap();
+    assert_eq!(meta.id, "11111111111111111111");
+
+    let data = Data {
+        name: "cargo".into(),
+        count: 7,
+    };
+    assert!(client.update(&meta.id, &data).is_ok());
+
+    let all = client.read().all::<Data>().unwrap();
+    assert_eq!(all.len(), 1);
+    assert_eq!(all[0].0.name, "cargo");
+    assert_eq!(all[0].1.updated_on, "2019-09-22T12:25:52.114Z");
+
+    assert!(client.delete(&meta.id).is_ok());
+
+    let res = client.read().id::<Data>(&meta.id);
+    assert_matches!(res.unwrap_err(), Error::General { code, message: _ } if code == 400);
+
+    let res = client.read().id::<Data>(&meta.id);
+    assert_matches!(res.unwrap_err(), Error::Cassette { message: _ });
+}
+
+#[test]
+fn test_record_and_replay() {
+    let _m = mock("GET", "/c1111111111111111111/11111111111111111111")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"_id":"11111111111111111111","name":"kuy","count":42,"_createdOn":"2019-09-22T12:24:37.513Z"}"#)
+        .create();
+    let path = std::env::temp_dir().join("jsonbox-test-record-and-replay.json");
+
+    let server_url = mockito::server_url();
+    let cassette = Cassette::record(&path);
+    let client = Client::new("c1111111111111111111")
+        .with_base_url(&server_url)
+        .with_middleware(cassette.clone());
+    let (data, _) = client.read().id::<Data>("11111111111111111111").unwrap();
+    assert_eq!(data.name, "kuy");
+    assert_eq!(cassette.len(), 1);
+    cassette.save().unwrap();
+
+    let cassette = Cassette::replay(&path).unwrap();
+    let client = Client::new("c1111111111111111111")
+        .with_base_url("http://127.0.0.1:9")
+        .with_middleware(cassette);
+    let (data, meta) = client.read().id::<Data>("11111111111111111111").unwrap();
+    assert_eq!(data.name, "kuy");
+    assert_eq!(data.count, 42);
+    assert_eq!(meta.created_on, "2019-09-22T12:24:37.513Z");
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -209,6 +209,19 @@ fn test_read_unknown_record_id() {
     assert_matches!(err, Error::General { code, message: _ } if code == 500);
 }
 
+#[test]
+fn test_read_non_json_error() {
+    let _m = mock("GET", "/e0000000000000000000/11111111111111111111")
+        .with_status(502)
+        .with_header("content-type", "text/html")
+        .with_body("<html><body>502 Bad Gateway</body></html>")
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("e0000000000000000000").with_base_url(&server_url);
+    let res = client.read().id::<Data>("11111111111111111111");
+    assert_matches!(res, Err(Error::Json { ref reason, .. }) if reason == "error");
+}
+
 #[test]
 fn test_update() {
     let _m = mock("PUT", "/00000000000000000000/33333333333333333333")
@@ -290,3 +303,24 @@ fn test_delete_unknown_record_id() {
     let err = res.unwrap_err();
     assert_matches!(err, Error::General { code, message: _ } if code == 400);
 }
+
+#[test]
+fn test_metrics() {
+    let _m = mock("GET", "/55555555555555555555/11111111111111111111")
+        .with_status(500)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Cannot read property '_id' of null"}"#)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("55555555555555555555").with_base_url(&server_url);
+    assert!(client.read().id::<Data>("11111111111111111111").is_err());
+
+    let metrics = client.metrics();
+    let read = metrics.operation("read_by_id").unwrap();
+    assert_eq!(read.count, 1);
+    assert_eq!(read.errors.get("server"), Some(&1));
+    assert_eq!(read.latency.count, 1);
+    assert!(metrics
+        .to_prometheus()
+        .contains("jsonbox_errors_total{operation=\"read_by_id\",class=\"server\"} 1\n"));
+}
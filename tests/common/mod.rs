@@ -0,0 +1,56 @@
+#![allow(dead_code)]
+
+use jsonbox::header::HeaderMap;
+use jsonbox::{Middleware, Next, Request, Response};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// A middleware serving scripted responses in order, without touching the network.
+///
+/// Every request is logged as `METHOD /path?query body`.
+#[derive(Clone, Default)]
+pub struct Script {
+    responses: Arc<Mutex<VecDeque<(u16, String)>>>,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Script {
+    pub fn new() -> Script {
+        Script::default()
+    }
+
+    pub fn push<S: Into<String>>(&self, status: u16, body: S) -> &Script {
+        self.responses
+            .lock()
+            .unwrap()
+            .push_back((status, body.into()));
+        self
+    }
+
+    pub fn log(&self) -> Vec<String> {
+        self.log.lock().unwrap().clone()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.responses.lock().unwrap().len()
+    }
+}
+
+impl Middleware for Script {
+    fn handle(&self, req: Request, _: Next) -> jsonbox::Result<Response> {
+        let path = req.url.splitn(4, '/').nth(3).unwrap_or("");
+        let entry = format!("{} /{} {}", req.method, path, req.body.unwrap_or_default());
+        self.log.lock().unwrap().push(entry.trim_end().to_string());
+        let (status, body) = self
+            .responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .expect("unexpected request");
+        Ok(Response {
+            status,
+            headers: HeaderMap::new(),
+            body,
+        })
+    }
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use common::Script;
+use jsonbox::{Client, CopyOptions, Error};
+use matches::*;
+
+fn record(id: &str, name: &str) -> String {
+    format!(
+        r#"{{"_id":"{}","name":"{}","_createdOn":"2019-09-22T12:00:00.000Z"}}"#,
+        id, name
+    )
+}
+
+#[test]
+fn test_copy_to() {
+    let source = Script::new();
+    source
+        .push(
+            200,
+            format!(
+                "[{},{}]",
+                record("11111111111111111111", "a"),
+                record("22222222222222222222", "b")
+            ),
+        )
+        .push(200, format!("[{}]", record("33333333333333333333", "c")));
+    let target = Script::new();
+    target
+        .push(200, "[]")
+        .push(
+            200,
+            format!(
+                "[{},{}]",
+                record("aaaaaaaaaaaaaaaaaaaa", "a"),
+                record("bbbbbbbbbbbbbbbbbbbb", "b")
+            ),
+        )
+        .push(200, format!("[{}]", record("cccccccccccccccccccc", "c")))
+        .push(
+            200,
+            format!(
+                "[{},{},{}]",
+                record("aaaaaaaaaaaaaaaaaaaa", "a"),
+                record("bbbbbbbbbbbbbbbbbbbb", "b"),
+                record("cccccccccccccccccccc", "c")
+            ),
+        );
+    let from = Client::new("p0000000000000000000").with_middleware(source.clone());
+    let to = Client::new("p1111111111111111111").with_middleware(target.clone());
+
+    let options = CopyOptions::new().page_size(2).preserve_meta("original");
+    let report = from.copy_to(&to, &options).unwrap();
+    assert_eq!(report.read, 3);
+    assert_eq!(
+        report.ids[2],
+        (
+            "33333333333333333333".to_string(),
+            "cccccccccccccccccccc".to_string()
+        )
+    );
+    assert_eq!(
+        source.log(),
+        vec![
+            "GET /p0000000000000000000?sort=_createdOn&skip=0&limit=2",
+            "GET /p0000000000000000000?sort=_createdOn&skip=2&limit=2",
+        ]
+    );
+    assert_eq!(
+        target.log()[2],
+        r#"POST /p1111111111111111111 [{"name":"c","original":{"created_on":"2019-09-22T12:00:00.000Z","id":"33333333333333333333","updated_on":"2019-09-22T12:00:00.000Z"}}]"#
+    );
+}
+
+#[test]
+fn test_copy_interrupted() {
+    let source = Script::new();
+    source.push(200, format!("[{}]", record("11111111111111111111", "a")));
+    let target = Script::new();
+    target.push(500, r#"{"message":"Internal Server Error"}"#);
+    let from = Client::new("p2222222222222222222").with_middleware(source);
+    let to = Client::new("p3333333333333333333").with_middleware(target);
+
+    let res = from.copy_to(&to, &CopyOptions::new().verify(false));
+    assert_matches!(
+        res,
+        Err(Error::Interrupted {
+            checkpoint: None,
+            ..
+        })
+    );
+
+    let res = from.copy_to(&to, &CopyOptions::new().preserve_meta("_meta"));
+    assert_matches!(res, Err(Error::ReservedKey { .. }));
+}
+
+#[test]
+fn test_copy_count_mismatch() {
+    let source = Script::new();
+    source.push(200, format!("[{}]", record("11111111111111111111", "a")));
+    let target = Script::new();
+    target
+        .push(200, "[]")
+        .push(200, format!("[{}]", record("aaaaaaaaaaaaaaaaaaaa", "a")))
+        .push(200, "[]");
+    let from = Client::new("p4444444444444444444").with_middleware(source);
+    let to = Client::new("p5555555555555555555").with_middleware(target);
+
+    let res = from.copy_to(&to, &CopyOptions::new());
+    assert_matches!(
+        res,
+        Err(Error::CountMismatch {
+            expected: 1,
+            actual: 0
+        })
+    );
+}
+
+#[test]
+fn test_copy_resume() {
+    let source = Script::new();
+    source.push(
+        200,
+        format!(
+            "[{},{}]",
+            record("11111111111111111111", "a"),
+            record("22222222222222222222", "b")
+        ),
+    );
+    let target = Script::new();
+    target
+        .push(200, format!("[{}]", record("aaaaaaaaaaaaaaaaaaaa", "a")))
+        .push(200, format!("[{}]", record("bbbbbbbbbbbbbbbbbbbb", "b")))
+        .push(
+            200,
+            format!(
+                "[{},{}]",
+                record("aaaaaaaaaaaaaaaaaaaa", "a"),
+                record("bbbbbbbbbbbbbbbbbbbb", "b")
+            ),
+        );
+    let from = Client::new("p6666666666666666666").with_middleware(source);
+    let to = Client::new("p7777777777777777777").with_middleware(target.clone());
+
+    let options = CopyOptions::new().resume_from("11111111111111111111");
+    let report = from.copy_to(&to, &options).unwrap();
+    assert_eq!(report.read, 1);
+    assert_eq!(
+        report.ids,
+        vec![(
+            "22222222222222222222".to_string(),
+            "bbbbbbbbbbbbbbbbbbbb".to_string()
+        )]
+    );
+    assert_eq!(
+        target.log()[1],
+        r#"POST /p7777777777777777777 [{"name":"b"}]"#
+    );
+}
+
+#[test]
+fn test_copy_resume_missing_checkpoint() {
+    let source = Script::new();
+    source.push(200, format!("[{}]", record("11111111111111111111", "a")));
+    let from = Client::new("p8888888888888888888").with_middleware(source);
+    let to = Client::new("p9999999999999999999").with_middleware(Script::new());
+
+    let options = CopyOptions::new()
+        .verify(false)
+        .resume_from("zzzzzzzzzzzzzzzzzzzz");
+    let res = from.copy_to(&to, &options);
+    assert_matches!(res, Err(Error::CheckpointNotFound { .. }));
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::Script;
+use jsonbox::{Client, Cursor, Error};
+use matches::*;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+struct Item {
+    n: u32,
+}
+
+fn item(id: &str, n: u32) -> String {
+    format!(
+        r#"{{"_id":"{}","n":{},"_createdOn":"2019-09-22T12:00:00.000Z"}}"#,
+        id, n
+    )
+}
+
+#[test]
+fn test_page_after() {
+    let script = Script::new();
+    script
+        .push(
+            200,
+            format!(
+                "[{},{},{}]",
+                item("11111111111111111111", 1),
+                item("22222222222222222222", 2),
+                item("33333333333333333333", 3)
+            ),
+        )
+        .push(
+            200,
+            format!(
+                "[{},{}]",
+                item("22222222222222222222", 2),
+                item("33333333333333333333", 3)
+            ),
+        );
+    let client = Client::new("c0000000000000000000").with_middleware(script.clone());
+    let mut query = client.read();
+    query.order_by("n").limit(2);
+
+    let page = query.page_after::<Item>(None).unwrap();
+    let ids: Vec<&str> = page.records.iter().map(|(_, m)| m.id.as_str()).collect();
+    assert_eq!(ids, vec!["11111111111111111111", "22222222222222222222"]);
+
+    // The cursor survives a round trip through its string form.
+    let cursor: Cursor = page.next.unwrap().to_string().parse().unwrap();
+    let page = query.page_after::<Item>(Some(&cursor)).unwrap();
+    let ns: Vec<u32> = page.records.iter().map(|(r, _)| r.n).collect();
+    assert_eq!(ns, vec![3]);
+    assert!(page.next.is_none());
+
+    assert_eq!(
+        script.log(),
+        vec![
+            "GET /c0000000000000000000?sort=n&skip=0&limit=3",
+            "GET /c0000000000000000000?sort=n&skip=0&limit=3&q=n:>=2",
+        ]
+    );
+}
+
+#[test]
+fn test_page_after_edge_run() {
+    let [a, b, c, d, e] = [
+        item("aaaaaaaaaaaaaaaaaaaa", 2),
+        item("bbbbbbbbbbbbbbbbbbbb", 2),
+        item("cccccccccccccccccccc", 3),
+        item("dddddddddddddddddddd", 3),
+        item("eeeeeeeeeeeeeeeeeeee", 3),
+    ];
+    let script = Script::new();
+    script
+        .push(200, format!("[{},{},{}]", a, b, c))
+        .push(200, format!("[{},{},{}]", a, b, c))
+        .push(200, format!("[{},{},{},{},{}]", a, b, c, d, e))
+        .push(200, format!("[{},{},{}]", c, d, e))
+        .push(200, format!("[{},{},{}]", c, d, e));
+    let client = Client::new("c2222222222222222222").with_middleware(script.clone());
+    let mut query = client.read();
+    query.order_by("n").limit(2);
+
+    let mut ids = vec![];
+    let mut cursor = None;
+    loop {
+        let page = query.page_after::<Item>(cursor.as_ref()).unwrap();
+        ids.extend(page.records.into_iter().map(|(_, m)| m.id[..1].to_string()));
+        match page.next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(ids, vec!["a", "b", "c", "d", "e"]);
+
+    assert_eq!(
+        script.log(),
+        vec![
+            "GET /c2222222222222222222?sort=n&skip=0&limit=3",
+            "GET /c2222222222222222222?sort=n&skip=0&limit=3&q=n:>=2",
+            "GET /c2222222222222222222?sort=n&skip=0&limit=6&q=n:>=2",
+            "GET /c2222222222222222222?sort=n&skip=0&limit=3&q=n:>=3",
+            "GET /c2222222222222222222?sort=n&skip=0&limit=6&q=n:>=3",
+        ]
+    );
+}
+
+#[test]
+fn test_page_after_wrong_field() {
+    let script = Script::new();
+    script.push(
+        200,
+        format!(
+            "[{},{}]",
+            item("11111111111111111111", 1),
+            item("22222222222222222222", 2)
+        ),
+    );
+    let client = Client::new("c1111111111111111111").with_middleware(script);
+    let mut query = client.read();
+    query.order_by("n").limit(1);
+    let cursor = query.page_after::<Item>(None).unwrap().next.unwrap();
+
+    query.order_by("m");
+    let res = query.page_after::<Item>(Some(&cursor));
+    assert_matches!(res, Err(Error::InvalidCursor { .. }));
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::Script;
+use jsonbox::{diff, Client, MatchBy, PatchOperation};
+use serde_json::json;
+use std::time::Duration;
+
+#[test]
+fn test_diff() {
+    let a = Script::new();
+    a.push(
+        200,
+        r#"[{"_id":"a1111111111111111111","sku":"x-1","price":10,"_createdOn":"2019-09-22T12:00:00.000Z"},
+            {"_id":"a2222222222222222222","sku":"x-2","price":20,"_createdOn":"2019-09-22T12:00:01.000Z"},
+            {"_id":"a3333333333333333333","sku":"x-3","price":30,"_createdOn":"2019-09-22T12:00:02.000Z"}]"#,
+    );
+    let b = Script::new();
+    b.push(
+        200,
+        r#"[{"_id":"b1111111111111111111","sku":"x-1","price":10,"_createdOn":"2019-09-23T12:00:00.000Z"},
+            {"_id":"b2222222222222222222","sku":"x-2","price":25,"_createdOn":"2019-09-23T12:00:01.000Z"},
+            {"_id":"b4444444444444444444","sku":"x-4","price":40,"_createdOn":"2019-09-23T12:00:02.000Z"},
+            {"_id":"b5555555555555555555","price":50,"_createdOn":"2019-09-23T12:00:03.000Z"}]"#,
+    );
+    let client_a = Client::new("d0000000000000000000").with_middleware(a);
+    let client_b = Client::new("d1111111111111111111").with_middleware(b);
+
+    let report = diff(&client_a, &client_b, &MatchBy::Key("sku".into())).unwrap();
+    assert!(!report.is_empty());
+    assert_eq!(report.added.len(), 1);
+    assert_eq!(report.added[0].key, "x-4");
+    assert_eq!(report.removed.len(), 1);
+    assert_eq!(report.removed[0].id, "a3333333333333333333");
+    assert_eq!(report.changed.len(), 1);
+    assert_eq!(
+        report.changed[0].changes,
+        vec![PatchOperation::Replace {
+            path: "/price".into(),
+            value: json!(25),
+        }]
+    );
+    assert_eq!(report.unmatched, vec!["b5555555555555555555"]);
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(
+        json["changed"][0],
+        json!({
+            "key": "x-2",
+            "id_a": "a2222222222222222222",
+            "id_b": "b2222222222222222222",
+            "changes": [{ "op": "replace", "path": "/price", "value": 25 }],
+        })
+    );
+}
+
+#[test]
+fn test_diff_bypasses_cache() {
+    let a = Script::new();
+    a.push(
+        200,
+        r#"[{"_id":"a1111111111111111111","sku":"x-1","price":10,"_createdOn":"2019-09-22T12:00:00.000Z"}]"#,
+    )
+    .push(
+        200,
+        r#"[{"_id":"a1111111111111111111","sku":"x-1","price":15,"_createdOn":"2019-09-22T12:00:00.000Z","_updatedOn":"2019-09-24T12:00:00.000Z"}]"#,
+    );
+    let b = Script::new();
+    b.push(
+        200,
+        r#"[{"_id":"b1111111111111111111","sku":"x-1","price":10,"_createdOn":"2019-09-23T12:00:00.000Z"}]"#,
+    )
+    .push(
+        200,
+        r#"[{"_id":"b1111111111111111111","sku":"x-1","price":10,"_createdOn":"2019-09-23T12:00:00.000Z"}]"#,
+    );
+    let client_a = Client::new("d2222222222222222222")
+        .with_middleware(a.clone())
+        .with_cache(10, Duration::from_secs(60));
+    let client_b = Client::new("d3333333333333333333")
+        .with_middleware(b.clone())
+        .with_cache(10, Duration::from_secs(60));
+
+    let key = MatchBy::Key("sku".into());
+    assert!(diff(&client_a, &client_b, &key).unwrap().is_empty());
+    let report = diff(&client_a, &client_b, &key).unwrap();
+    assert_eq!(report.changed.len(), 1);
+    assert_eq!(a.remaining(), 0);
+    assert_eq!(b.remaining(), 0);
+}
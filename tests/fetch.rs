@@ -0,0 +1,95 @@
+use jsonbox::header::HeaderMap;
+use jsonbox::{Client, Next, Request, Response};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug)]
+struct Item {
+    n: usize,
+}
+
+fn param(url: &str, name: &str) -> usize {
+    url.split(['?', '&'])
+        .find_map(|p| p.strip_prefix(&format!("{}=", name)))
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+/// Serve a box of `total` records, as if `shift` records were created before the rest
+/// once `skip` reaches `shift_at`.
+fn serve(
+    total: usize,
+    shift: usize,
+    shift_at: usize,
+    requests: Arc<AtomicUsize>,
+) -> Client<'static> {
+    Client::new("f0000000000000000000").with_middleware(move |req: Request, _: Next| {
+        requests.fetch_add(1, Ordering::SeqCst);
+        let (skip, limit) = (param(&req.url, "skip"), param(&req.url, "limit"));
+        let skip = if skip >= shift_at { skip - shift } else { skip };
+        let records: Vec<String> = (skip..(skip + limit).min(total))
+            .map(|n| {
+                format!(
+                    r#"{{"_id":"{:020}","n":{},"_createdOn":"2019-09-22T12:00:00.000Z"}}"#,
+                    n, n
+                )
+            })
+            .collect();
+        Ok(Response {
+            status: 200,
+            headers: HeaderMap::new(),
+            body: format!("[{}]", records.join(",")),
+        })
+    })
+}
+
+#[test]
+fn test_fetch_all_parallel() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let client = serve(45, 0, usize::MAX, requests.clone());
+    let mut query = client.read();
+    query.order_by("n").limit(10);
+
+    let records = query.fetch_all_parallel::<Item>(4).unwrap();
+    let ns: Vec<usize> = records.iter().map(|(r, _)| r.n).collect();
+    assert_eq!(ns, (0..45).collect::<Vec<_>>());
+    // Probes of pages 0, 1, 2, 4, 8, 6, 5, then 5 pages.
+    assert_eq!(requests.load(Ordering::SeqCst), 12);
+
+    let records = query.fetch_all_parallel::<Item>(1).unwrap();
+    assert_eq!(records.len(), 45);
+}
+
+#[test]
+fn test_fetch_all_parallel_dedup() {
+    let client = serve(40, 1, 10, Arc::new(AtomicUsize::new(0)));
+    let mut query = client.read();
+    query.order_by("n").limit(10);
+
+    let records = query.fetch_all_parallel::<Item>(4).unwrap();
+    let ns: Vec<usize> = records.iter().map(|(r, _)| r.n).collect();
+    assert_eq!(ns, (0..40).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_fetch_all_parallel_empty() {
+    let client = serve(0, 0, usize::MAX, Arc::new(AtomicUsize::new(0)));
+    let records = client.read().fetch_all_parallel::<Item>(4).unwrap();
+    assert!(records.is_empty());
+}
+
+#[test]
+fn test_fetch_all_parallel_bypasses_cache() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let client = serve(45, 0, usize::MAX, requests.clone()).with_cache(64, Duration::from_secs(60));
+    let mut query = client.read();
+    query.order_by("n").limit(10);
+
+    assert_eq!(query.fetch_all_parallel::<Item>(4).unwrap().len(), 45);
+    assert_eq!(query.fetch_all_parallel::<Item>(4).unwrap().len(), 45);
+    // Probes and pages are all sent again.
+    assert_eq!(requests.load(Ordering::SeqCst), 24);
+}
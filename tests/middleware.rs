@@ -0,0 +1,84 @@
+use jsonbox::header::{HeaderMap, HeaderValue};
+use jsonbox::{Client, Error, Method, Next, Request, Response};
+use matches::*;
+use mockito::mock;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Data {
+    name: String,
+    count: i32,
+}
+
+#[test]
+fn test_inject_header() {
+    let _m = mock("DELETE", "/m0000000000000000000/11111111111111111111")
+        .match_header("x-correlation-id", "42")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Record removed."}"#)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("m0000000000000000000")
+        .with_base_url(&server_url)
+        .with_middleware(|mut req: Request, next: Next| {
+            req.headers
+                .insert("x-correlation-id", HeaderValue::from_static("42"));
+            next.run(req)
+        });
+    let res = client.delete("11111111111111111111");
+    assert!(res.is_ok());
+}
+
+#[test]
+fn test_observe_query_builder() {
+    let _m = mock("GET", "/m1111111111111111111?sort=-_createdOn&skip=0&limit=1")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"[{"_id":"11111111111111111111","name":"kuy","count":42,"_createdOn":"2019-09-23T12:24:37.513Z"}]"#)
+        .create();
+    let server_url = mockito::server_url();
+    let seen = Arc::new(Mutex::new(vec![]));
+    let log = seen.clone();
+    let client = Client::new("m1111111111111111111")
+        .with_base_url(&server_url)
+        .with_middleware(move |req: Request, next: Next| {
+            let url = req.url.clone();
+            let res = next.run(req)?;
+            log.lock().unwrap().push((url, res.status));
+            Ok(res)
+        });
+    let res = client.read().limit(1).run::<Data>();
+    assert!(res.is_ok());
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 1);
+    assert!(seen[0]
+        .0
+        .ends_with("/m1111111111111111111?sort=-_createdOn&skip=0&limit=1"));
+    assert_eq!(seen[0].1, 200);
+}
+
+#[test]
+fn test_short_circuit() {
+    let client = Client::new("m2222222222222222222")
+        .with_base_url("http://127.0.0.1:9")
+        .with_middleware(|req: Request, _: Next| {
+            assert_eq!(req.method, Method::PUT);
+            Ok(Response {
+                status: 403,
+                headers: HeaderMap::new(),
+                body: r#"{"message":"Read only"}"#.into(),
+            })
+        });
+    let data = Data {
+        name: "cargo".into(),
+        count: 42,
+    };
+    let res = client.update("11111111111111111111", &data);
+    assert!(res.is_err());
+
+    let err = res.unwrap_err();
+    assert_matches!(err, Error::General { code, message } if code == 403 && message == "Read only");
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use common::Script;
+use jsonbox::{Client, Error, MigrationOptions};
+use matches::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+const PAGE1: &str = r#"[{"_id":"11111111111111111111","name":"a","count":1,"_createdOn":"2019-09-22T12:24:37.513Z"},{"_id":"22222222222222222222","name":"b","count":20,"_createdOn":"2019-09-22T12:24:38.513Z"}]"#;
+const PAGE2: &str = r#"[{"_id":"33333333333333333333","name":"c","count":3,"_createdOn":"2019-09-22T12:24:39.513Z"}]"#;
+const UPDATED: &str = r#"{"message":"Record updated."}"#;
+
+fn bump(record: &mut Value) {
+    if record["count"].as_i64().unwrap() < 10 {
+        record["count"] = json!(10);
+    }
+}
+
+#[test]
+fn test_for_each_update() {
+    let script = Script::new();
+    script
+        .push(200, PAGE1)
+        .push(200, UPDATED)
+        .push(200, PAGE2)
+        .push(200, UPDATED);
+    let client = Client::new("g0000000000000000000").with_middleware(script.clone());
+    let report = client.read().limit(2).for_each_update(bump).unwrap();
+    assert_eq!(report.scanned, 3);
+    assert_eq!(report.changed, 2);
+    assert_eq!(report.checkpoint, Some("33333333333333333333".into()));
+    assert_eq!(
+        script.log(),
+        vec![
+            "GET /g0000000000000000000?sort=-_createdOn&skip=0&limit=2",
+            r#"PUT /g0000000000000000000/11111111111111111111 {"count":10,"name":"a"}"#,
+            "GET /g0000000000000000000?sort=-_createdOn&skip=2&limit=2",
+            r#"PUT /g0000000000000000000/33333333333333333333 {"count":10,"name":"c"}"#,
+        ]
+    );
+}
+
+#[test]
+fn test_for_each_update_dry_run() {
+    let script = Script::new();
+    script.push(200, PAGE1).push(200, PAGE2);
+    let client = Client::new("g0000000000000000000").with_middleware(script.clone());
+    let options = MigrationOptions::new().dry_run();
+    let report = client
+        .read()
+        .limit(2)
+        .for_each_update_with(&options, bump)
+        .unwrap();
+    assert_eq!(report.changed, 2);
+    assert_eq!(report.diffs.len(), 2);
+    assert_eq!(report.diffs[1].0, "33333333333333333333");
+    assert_eq!(
+        serde_json::to_value(&report.diffs[1].1).unwrap(),
+        json!([{"op":"replace","path":"/count","value":10}])
+    );
+    assert_eq!(script.log().len(), 2);
+}
+
+#[test]
+fn test_for_each_update_interrupted_and_resumed() {
+    let script = Script::new();
+    script
+        .push(200, PAGE1)
+        .push(200, UPDATED)
+        .push(500, r#"{"message":"Internal error"}"#);
+    let client = Client::new("g0000000000000000000").with_middleware(script.clone());
+    let res = client.read().limit(2).for_each_update(bump);
+    let checkpoint = match res {
+        Err(Error::Interrupted { checkpoint, source }) => {
+            assert_matches!(*source, Error::General { code, .. } if code == 500);
+            checkpoint.unwrap()
+        }
+        _ => panic!("expected interruption"),
+    };
+    assert_eq!(checkpoint, "22222222222222222222");
+
+    script.push(200, PAGE1).push(200, PAGE2).push(200, UPDATED);
+    let options = MigrationOptions::new().resume_from(&checkpoint);
+    let report = client
+        .read()
+        .limit(2)
+        .for_each_update_with(&options, bump)
+        .unwrap();
+    assert_eq!(report.scanned, 1);
+    assert_eq!(report.changed, 1);
+    assert_eq!(
+        script.log().last().unwrap(),
+        r#"PUT /g0000000000000000000/33333333333333333333 {"count":10,"name":"c"}"#
+    );
+}
+
+#[test]
+fn test_resume_from_missing_checkpoint() {
+    let script = Script::new();
+    script.push(200, PAGE1).push(200, PAGE2);
+    let client = Client::new("g0000000000000000000").with_middleware(script.clone());
+    let options = MigrationOptions::new().resume_from("99999999999999999999");
+    let res = client.read().limit(2).for_each_update_with(&options, bump);
+    assert_matches!(
+        res,
+        Err(Error::CheckpointNotFound { ref checkpoint }) if checkpoint == "99999999999999999999"
+    );
+    assert_eq!(script.log().len(), 2);
+}
+
+#[derive(Deserialize)]
+struct V1 {
+    name: String,
+    count: i32,
+}
+
+#[derive(Serialize)]
+struct V2 {
+    title: String,
+    count: i32,
+}
+
+#[test]
+fn test_for_each_update_as() {
+    let script = Script::new();
+    script.push(200, PAGE2).push(200, UPDATED);
+    let client = Client::new("g0000000000000000000").with_middleware(script.clone());
+    let report = client
+        .read()
+        .for_each_update_as(&MigrationOptions::new(), |v1: V1| V2 {
+            title: v1.name.to_uppercase(),
+            count: v1.count,
+        })
+        .unwrap();
+    assert_eq!(report.changed, 1);
+    assert_eq!(
+        script.log().last().unwrap(),
+        r#"PUT /g0000000000000000000/33333333333333333333 {"count":3,"title":"C"}"#
+    );
+}
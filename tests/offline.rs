@@ -0,0 +1,188 @@
+mod common;
+
+use common::Script;
+use jsonbox::header::HeaderMap;
+use jsonbox::{Client, Middleware, Next, Request, Response};
+use serde::{Deserialize, Serialize};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Data {
+    name: String,
+    count: i32,
+}
+
+fn journal(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("jsonbox-{}-{}.jsonl", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Answer from `script` while online, otherwise let requests hit a closed port.
+fn switch(script: &Script, online: &Arc<AtomicBool>) -> impl Middleware {
+    let script = script.clone();
+    let online = online.clone();
+    move |req: Request, next: Next| {
+        if online.load(Ordering::SeqCst) {
+            script.handle(req, next)
+        } else {
+            next.run(req)
+        }
+    }
+}
+
+#[test]
+fn test_queue_and_flush() {
+    let path = journal("flush");
+    let script = Script::new();
+    let online = Arc::new(AtomicBool::new(false));
+    let client = Client::new("o0000000000000000000")
+        .with_base_url("http://127.0.0.1:9")
+        .with_middleware(switch(&script, &online))
+        .with_offline_journal(&path);
+
+    let data = Data {
+        name: "kuy".into(),
+        count: 42,
+    };
+    let (created, meta) = client.create(&data).unwrap();
+    assert_eq!(created, data);
+    assert!(meta.id.starts_with("local-"));
+
+    // Queued without trying the network, even once it's back, to keep the order.
+    online.store(true, Ordering::SeqCst);
+    let data = Data {
+        name: "kuy".into(),
+        count: 43,
+    };
+    client.update(&meta.id, &data).unwrap();
+    client.delete("22222222222222222222").unwrap();
+    assert!(script.log().is_empty());
+    assert_eq!(client.pending_writes().unwrap(), 3);
+
+    script
+        .push(
+            200,
+            r#"{"_id":"11111111111111111111","name":"kuy","count":42,"_createdOn":"2019-09-22T12:24:37.513Z"}"#,
+        )
+        .push(200, r#"{"message":"Record updated."}"#)
+        .push(404, r#"{"message":"Record not found."}"#);
+    let report = client.flush().unwrap();
+    assert_eq!(report.replayed, 2);
+    assert_eq!(report.ids[&meta.id], "11111111111111111111");
+    assert_eq!(report.rejected.len(), 1);
+    assert_eq!(report.rejected[0].0, "22222222222222222222");
+    assert_eq!(report.pending, 0);
+    assert_eq!(
+        script.log(),
+        vec![
+            r#"POST /o0000000000000000000 {"count":42,"name":"kuy"}"#,
+            r#"PUT /o0000000000000000000/11111111111111111111 {"count":43,"name":"kuy"}"#,
+            "DELETE /o0000000000000000000/22222222222222222222",
+        ]
+    );
+    assert_eq!(client.pending_writes().unwrap(), 0);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_flush_interrupted() {
+    let path = journal("interrupted");
+    let script = Script::new();
+    let online = Arc::new(AtomicBool::new(false));
+    let client = Client::new("o1111111111111111111")
+        .with_base_url("http://127.0.0.1:9")
+        .with_middleware(switch(&script, &online))
+        .with_offline_journal(&path);
+
+    client.delete("11111111111111111111").unwrap();
+    client.delete("22222222222222222222").unwrap();
+
+    let report = client.flush().unwrap();
+    assert_eq!(report.replayed, 0);
+    assert_eq!(report.pending, 2);
+
+    // Another client picks up the journal left behind.
+    online.store(true, Ordering::SeqCst);
+    script
+        .push(200, r#"{"message":"Record removed."}"#)
+        .push(200, r#"{"message":"Record removed."}"#);
+    let client = Client::new("o1111111111111111111")
+        .with_middleware(switch(&script, &online))
+        .with_offline_journal(&path);
+    let report = client.flush().unwrap();
+    assert_eq!(report.replayed, 2);
+    assert_eq!(script.log().len(), 2);
+}
+
+#[test]
+fn test_online_write() {
+    let path = journal("online");
+    let script = Script::new();
+    script.push(200, r#"{"message":"Record removed."}"#);
+    let online = Arc::new(AtomicBool::new(true));
+    let client = Client::new("o2222222222222222222")
+        .with_middleware(switch(&script, &online))
+        .with_offline_journal(&path);
+
+    client.delete("11111111111111111111").unwrap();
+    assert_eq!(script.log().len(), 1);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_concurrent_online_writes() {
+    let path = journal("concurrent");
+    let (running, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+    let (r, p) = (running.clone(), peak.clone());
+    let client = Client::new("o3333333333333333333")
+        .with_middleware(move |_: Request, _: Next| {
+            let now = r.fetch_add(1, Ordering::SeqCst) + 1;
+            p.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(50));
+            r.fetch_sub(1, Ordering::SeqCst);
+            Ok(Response {
+                status: 200,
+                headers: HeaderMap::new(),
+                body: r#"{"message":"Record removed."}"#.into(),
+            })
+        })
+        .with_offline_journal(&path);
+
+    let ids = vec![
+        "11111111111111111111",
+        "22222222222222222222",
+        "33333333333333333333",
+    ];
+    assert!(client.delete_many(ids, 3).is_ok());
+    assert!(peak.load(Ordering::SeqCst) > 1);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_panic_in_middleware() {
+    let path = journal("panic");
+    let script = Script::new();
+    script.push(200, r#"{"message":"Record removed."}"#);
+    let panicked = Arc::new(AtomicBool::new(false));
+    let first = panicked.clone();
+    let client = Client::new("o4444444444444444444")
+        .with_middleware(move |req: Request, next: Next| {
+            if !first.swap(true, Ordering::SeqCst) {
+                panic!("middleware failure");
+            }
+            next.run(req)
+        })
+        .with_middleware(script.clone())
+        .with_offline_journal(&path);
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| client.delete("11111111111111111111")));
+    assert!(res.is_err());
+    client.delete("11111111111111111111").unwrap();
+    assert_eq!(script.log().len(), 1);
+}
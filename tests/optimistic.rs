@@ -0,0 +1,121 @@
+mod common;
+
+use common::Script;
+use jsonbox::{Client, Error};
+use matches::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Data {
+    name: String,
+    count: i32,
+}
+
+const UPDATED: &str = r#"{"message":"Record updated."}"#;
+
+fn record(count: i32, updated_on: &str) -> String {
+    format!(
+        r#"{{"_id":"11111111111111111111","name":"kuy","count":{},"_createdOn":"2019-09-22T12:24:37.513Z","_updatedOn":"{}"}}"#,
+        count, updated_on
+    )
+}
+
+#[test]
+fn test_update_if_unmodified() {
+    let script = Script::new();
+    script
+        .push(200, record(42, "2019-09-22T12:25:52.114Z"))
+        .push(200, UPDATED);
+    let client = Client::new("o0000000000000000000").with_middleware(script.clone());
+    let data = Data {
+        name: "kuy".into(),
+        count: 43,
+    };
+    let res =
+        client.update_if_unmodified("11111111111111111111", "2019-09-22T12:25:52.114Z", &data);
+    assert!(res.is_ok());
+    assert_eq!(
+        script.log(),
+        vec![
+            "GET /o0000000000000000000/11111111111111111111",
+            r#"PUT /o0000000000000000000/11111111111111111111 {"name":"kuy","count":43}"#
+        ]
+    );
+}
+
+#[test]
+fn test_update_if_unmodified_conflict() {
+    let script = Script::new();
+    script.push(200, record(42, "2019-09-22T12:30:00.000Z"));
+    let client = Client::new("o0000000000000000000").with_middleware(script.clone());
+    let data = Data {
+        name: "kuy".into(),
+        count: 43,
+    };
+    let res =
+        client.update_if_unmodified("11111111111111111111", "2019-09-22T12:25:52.114Z", &data);
+    assert_matches!(
+        res,
+        Err(Error::Conflict { ref actual, .. }) if actual == "2019-09-22T12:30:00.000Z"
+    );
+    assert_eq!(script.log().len(), 1);
+}
+
+#[test]
+fn test_modify_retry() {
+    let script = Script::new();
+    script
+        .push(200, record(42, "2019-09-22T12:25:52.114Z"))
+        .push(200, record(50, "2019-09-22T12:30:00.000Z"))
+        .push(200, record(50, "2019-09-22T12:30:00.000Z"))
+        .push(200, record(50, "2019-09-22T12:30:00.000Z"))
+        .push(200, UPDATED);
+    let client = Client::new("o0000000000000000000").with_middleware(script.clone());
+    let data = client
+        .modify("11111111111111111111", |data: &mut Data| data.count += 1)
+        .unwrap();
+    assert_eq!(data.count, 51);
+    assert_eq!(
+        script.log().last().unwrap(),
+        r#"PUT /o0000000000000000000/11111111111111111111 {"name":"kuy","count":51}"#
+    );
+    assert_eq!(client.metrics().operation("update").unwrap().retries, 1);
+}
+
+#[test]
+fn test_modify_value() {
+    let script = Script::new();
+    script
+        .push(200, record(42, "2019-09-22T12:25:52.114Z"))
+        .push(200, record(42, "2019-09-22T12:25:52.114Z"))
+        .push(200, UPDATED);
+    let client = Client::new("o0000000000000000000").with_middleware(script.clone());
+    let data = client
+        .modify("11111111111111111111", |data: &mut Value| {
+            assert!(data.get("_id").is_none());
+            data["count"] = json!(43);
+        })
+        .unwrap();
+    assert_eq!(data, json!({"name": "kuy", "count": 43}));
+    assert_eq!(
+        script.log().last().unwrap(),
+        r#"PUT /o0000000000000000000/11111111111111111111 {"count":43,"name":"kuy"}"#
+    );
+}
+
+#[test]
+fn test_modify_give_up() {
+    let script = Script::new();
+    script
+        .push(200, record(42, "2019-09-22T12:25:52.114Z"))
+        .push(200, record(50, "2019-09-22T12:30:00.000Z"))
+        .push(200, record(50, "2019-09-22T12:30:00.000Z"))
+        .push(200, record(60, "2019-09-22T12:35:00.000Z"));
+    let client = Client::new("o0000000000000000000")
+        .with_middleware(script.clone())
+        .with_conflict_retries(1);
+    let res = client.modify("11111111111111111111", |data: &mut Data| data.count += 1);
+    assert_matches!(res, Err(Error::Conflict { .. }));
+    assert_eq!(script.remaining(), 0);
+}
@@ -0,0 +1,112 @@
+use jsonbox::{Client, Error, PatchOperation};
+use matches::*;
+use mockito::{mock, Matcher};
+use serde_json::json;
+
+const RECORD: &str = r#"{"_id":"11111111111111111111","name":"kuy","tags":["a"],"profile":{"city":"Tokyo","age":42},"_createdOn":"2019-09-22T12:24:37.513Z"}"#;
+
+#[test]
+fn test_patch() {
+    let _m1 = mock("GET", "/p0000000000000000000/11111111111111111111")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(RECORD)
+        .create();
+    let _m2 = mock("PUT", "/p0000000000000000000/11111111111111111111")
+        .match_body(Matcher::Json(
+            json!({"name":"kuy","tags":["b"],"profile":{"city":"Osaka"}}),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Record updated."}"#)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("p0000000000000000000").with_base_url(&server_url);
+    let patch = json!({"_id":"ignored","tags":["b"],"profile":{"city":"Osaka","age":null}});
+    let record = client.patch("11111111111111111111", &patch).unwrap();
+    assert_eq!(
+        record,
+        json!({"name":"kuy","tags":["b"],"profile":{"city":"Osaka"}})
+    );
+    _m2.assert();
+}
+
+#[test]
+fn test_patch_if_changed() {
+    let _m1 = mock("GET", "/p1111111111111111111/11111111111111111111")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(RECORD)
+        .create();
+    let _m2 = mock("PUT", "/p1111111111111111111/11111111111111111111")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Record updated."}"#)
+        .expect(0)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("p1111111111111111111").with_base_url(&server_url);
+    let patch = json!({"name":"kuy","profile":{"city":"Tokyo"}});
+    let res = client
+        .patch_if_changed("11111111111111111111", &patch)
+        .unwrap();
+    assert_eq!(res, None);
+    _m2.assert();
+}
+
+#[test]
+fn test_apply_patch() {
+    let _m1 = mock("GET", "/p2222222222222222222/11111111111111111111")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(RECORD)
+        .create();
+    let _m2 = mock("PUT", "/p2222222222222222222/11111111111111111111")
+        .match_body(Matcher::Json(
+            json!({"name":"kuy","tags":["a","b"],"city":"Tokyo","profile":{"age":42}}),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Record updated."}"#)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("p2222222222222222222").with_base_url(&server_url);
+    let ops: Vec<PatchOperation> = serde_json::from_value(json!([
+        {"op":"test","path":"/name","value":"kuy"},
+        {"op":"add","path":"/tags/-","value":"b"},
+        {"op":"move","from":"/profile/city","path":"/city"}
+    ]))
+    .unwrap();
+    let record = client.apply_patch("11111111111111111111", &ops).unwrap();
+    assert_eq!(record["city"], "Tokyo");
+    _m2.assert();
+}
+
+#[test]
+fn test_apply_patch_test_failed() {
+    let _m1 = mock("GET", "/p3333333333333333333/11111111111111111111")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(RECORD)
+        .create();
+    let _m2 = mock("PUT", "/p3333333333333333333/11111111111111111111")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Record updated."}"#)
+        .expect(0)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("p3333333333333333333").with_base_url(&server_url);
+    let ops = vec![
+        PatchOperation::Test {
+            path: "/name".into(),
+            value: json!("github"),
+        },
+        PatchOperation::Remove {
+            path: "/tags".into(),
+        },
+    ];
+    let res = client.apply_patch("11111111111111111111", &ops);
+    assert_matches!(res, Err(Error::PatchTest { ref path, .. }) if path == "/name");
+    _m2.assert();
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::Script;
+use jsonbox::Client;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+struct Name {
+    name: String,
+}
+
+const KUY: &str = r#"{"_id":"11111111111111111111","name":"kuy","count":42,"_createdOn":"2019-09-23T12:24:37.513Z"}"#;
+const GITHUB: &str = r#"{"_id":"22222222222222222222","name":"github","tags":["a","b"],"_createdOn":"2019-09-22T12:24:37.513Z","_updatedOn":"2019-09-24T12:24:37.513Z"}"#;
+
+#[test]
+fn test_run_raw() {
+    let script = Script::new();
+    script.push(200, format!("[{}, {}]", KUY, GITHUB));
+    let client = Client::new("r0000000000000000000").with_middleware(script);
+
+    let records = client.read().run_raw().unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].get(), KUY);
+    assert_eq!(records[1].parse::<Name>().unwrap().name, "github");
+    assert_eq!(
+        records[1].meta().unwrap().updated_on,
+        "2019-09-24T12:24:37.513Z"
+    );
+    assert_eq!(
+        serde_json::to_string(&records).unwrap(),
+        format!("[{},{}]", KUY, GITHUB)
+    );
+}
+
+#[test]
+fn test_id_raw() {
+    let script = Script::new();
+    script.push(200, KUY);
+    let client = Client::new("r1111111111111111111").with_middleware(script.clone());
+
+    let record = client.read().id_raw("11111111111111111111").unwrap();
+    assert_eq!(record.meta().unwrap().id, "11111111111111111111");
+    assert_eq!(record.into_raw().get(), KUY);
+    assert_eq!(
+        script.log(),
+        vec!["GET /r1111111111111111111/11111111111111111111"]
+    );
+}
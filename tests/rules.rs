@@ -0,0 +1,60 @@
+mod common;
+
+use common::Script;
+use jsonbox::{Client, Error};
+use matches::*;
+use serde_json::{json, Value};
+
+#[test]
+fn test_reject_reserved_keys() {
+    let script = Script::new();
+    let client = Client::new("r0000000000000000000").with_middleware(script.clone());
+
+    let res = client.create(&json!({ "name": "kuy", "$where": "1" }));
+    assert_matches!(res, Err(Error::ReservedKey { ref paths }) if paths == &["/$where"]);
+
+    let res = client.create_bulk(&[json!({ "name": "kuy" }), json!({ "meta": { "_v": 1 } })]);
+    assert_matches!(res, Err(Error::ReservedKey { ref paths }) if paths == &["/1/meta/_v"]);
+
+    assert!(script.log().is_empty());
+}
+
+#[test]
+fn test_reject_large_payload() {
+    let script = Script::new();
+    let client = Client::new("r1111111111111111111").with_middleware(script.clone());
+    let data = json!({ "text": "x".repeat(10 * 1024) });
+
+    let res = client.update("11111111111111111111", &data);
+    assert_matches!(res, Err(Error::PayloadTooLarge { size, limit }) if size > limit && limit == 10 * 1024);
+
+    let half = json!({ "text": "x".repeat(6 * 1024) });
+    let res = client.create_bulk(&[half.clone(), half]);
+    assert_matches!(res, Err(Error::PayloadTooLarge { .. }));
+
+    assert!(script.log().is_empty());
+}
+
+#[test]
+fn test_key_escaping() {
+    let script = Script::new();
+    script.push(
+        200,
+        r#"{"_id":"11111111111111111111","~_private":true,"~$price":1,"~~tilde":2,"_createdOn":"2019-09-22T12:24:37.513Z"}"#,
+    );
+    let client = Client::new("r2222222222222222222")
+        .with_middleware(script.clone())
+        .with_key_escaping();
+    let data = json!({ "_private": true, "$price": 1, "~tilde": 2 });
+
+    let (record, meta) = client.create_value(&data).unwrap();
+    assert_eq!(record, data);
+    assert_eq!(meta.id, "11111111111111111111");
+
+    let log = script.log();
+    let body: Value = serde_json::from_str(log[0].splitn(3, ' ').nth(2).unwrap()).unwrap();
+    assert_eq!(
+        body,
+        json!({ "~_private": true, "~$price": 1, "~~tilde": 2 })
+    );
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::Script;
+use jsonbox::{Client, Versioned};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct User {
+    schema_version: u64,
+    full_name: String,
+}
+
+impl Versioned for User {
+    fn upgrades() -> Vec<fn(Value) -> Value> {
+        vec![|mut v| {
+            v["full_name"] = v["name"].take();
+            v.as_object_mut().unwrap().remove("name");
+            v
+        }]
+    }
+}
+
+const RECORDS: &str = r#"[{"_id":"11111111111111111111","name":"kuy","_createdOn":"2019-09-22T12:24:37.513Z"},{"_id":"22222222222222222222","full_name":"Yuki","schema_version":1,"_createdOn":"2019-09-22T12:24:38.513Z"}]"#;
+
+#[test]
+fn test_read_upgraded() {
+    let script = Script::new();
+    script
+        .push(
+            200,
+            r#"{"_id":"11111111111111111111","name":"kuy","_createdOn":"2019-09-22T12:24:37.513Z"}"#,
+        )
+        .push(200, RECORDS);
+    let client = Client::new("s0000000000000000000")
+        .with_middleware(script.clone())
+        .with_schema::<User>();
+
+    let (user, meta) = client.read().id::<User>("11111111111111111111").unwrap();
+    assert_eq!(user.full_name, "kuy");
+    assert_eq!(user.schema_version, 1);
+    assert_eq!(meta.id, "11111111111111111111");
+
+    let all = client.read().all::<User>().unwrap();
+    assert_eq!(all[0].0.full_name, "kuy");
+    assert_eq!(all[1].0.full_name, "Yuki");
+}
+
+#[test]
+fn test_migrate_box() {
+    let script = Script::new();
+    script
+        .push(200, RECORDS)
+        .push(200, r#"{"message":"Record updated."}"#);
+    let client = Client::new("s0000000000000000000").with_middleware(script.clone());
+    let report = client.migrate_box::<User>().unwrap();
+    assert_eq!(report.scanned, 2);
+    assert_eq!(report.changed, 1);
+    assert_eq!(
+        script.log(),
+        vec![
+            "GET /s0000000000000000000?sort=-_createdOn&skip=0&limit=100",
+            r#"PUT /s0000000000000000000/11111111111111111111 {"full_name":"kuy","schema_version":1}"#,
+        ]
+    );
+}
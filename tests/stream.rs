@@ -0,0 +1,67 @@
+mod common;
+
+use common::Script;
+use jsonbox::{Client, Error};
+use matches::*;
+use mockito::mock;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    name: String,
+    count: i32,
+}
+
+const RECORDS: &str = r#"[{"_id":"11111111111111111111","name":"kuy","count":42,"_createdOn":"2019-09-23T12:24:37.513Z"},{"_id":"22222222222222222222","name":"github","count":7,"_createdOn":"2019-09-22T12:24:37.513Z"}]"#;
+
+#[test]
+fn test_stream() {
+    let _m = mock("GET", "/s0000000000000000000")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(RECORDS)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("s0000000000000000000").with_base_url(&server_url);
+
+    let mut records = client.read().stream::<Data>().unwrap();
+    let (data, meta) = records.next().unwrap().unwrap();
+    assert_eq!((data.name.as_str(), data.count), ("kuy", 42));
+    assert_eq!(meta.id, "11111111111111111111");
+    let (data, _) = records.next().unwrap().unwrap();
+    assert_eq!(data.name, "github");
+    assert!(records.next().is_none());
+}
+
+#[test]
+fn test_stream_error() {
+    let _m = mock("GET", "/s1111111111111111111")
+        .with_status(400)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Invalid Box ID"}"#)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("s1111111111111111111").with_base_url(&server_url);
+
+    let res = client.read().stream::<Data>();
+    assert_matches!(res.err(), Some(Error::General { code: 400, .. }));
+}
+
+#[test]
+fn test_stream_through_middleware() {
+    let script = Script::new();
+    script.push(200, RECORDS);
+    let client = Client::new("s2222222222222222222").with_middleware(script.clone());
+
+    let names: Vec<String> = client
+        .read()
+        .stream::<Data>()
+        .unwrap()
+        .map(|record| record.unwrap().0.name)
+        .collect();
+    assert_eq!(names, vec!["kuy", "github"]);
+    assert_eq!(
+        script.log(),
+        vec!["GET /s2222222222222222222?sort=-_createdOn&skip=0&limit=20"]
+    );
+}
@@ -0,0 +1,232 @@
+mod common;
+
+use common::Script;
+use jsonbox::{Client, Side, SyncAction, SyncOptions};
+use serde_json::json;
+use std::time::Duration;
+
+fn record(id: &str, email: &str, name: &str, updated_on: &str) -> String {
+    format!(
+        r#"{{"_id":"{}","email":"{}","name":"{}","_createdOn":"2019-09-22T12:00:00.000Z","_updatedOn":"{}"}}"#,
+        id, email, name, updated_on
+    )
+}
+
+fn boxes() -> (Script, Script) {
+    let source = Script::new();
+    source.push(
+        200,
+        format!(
+            "[{},{},{}]",
+            record(
+                "s1111111111111111111",
+                "a@example.com",
+                "A",
+                "2019-09-22T12:00:00.000Z"
+            ),
+            record(
+                "s2222222222222222222",
+                "b@example.com",
+                "B (new)",
+                "2019-09-23T12:00:00.000Z"
+            ),
+            record(
+                "s3333333333333333333",
+                "c@example.com",
+                "C",
+                "2019-09-22T12:00:00.000Z"
+            ),
+        ),
+    );
+    let target = Script::new();
+    target.push(
+        200,
+        format!(
+            "[{},{},{}]",
+            record(
+                "t1111111111111111111",
+                "a@example.com",
+                "A",
+                "2019-09-22T12:00:00.000Z"
+            ),
+            record(
+                "t2222222222222222222",
+                "b@example.com",
+                "B",
+                "2019-09-22T12:00:00.000Z"
+            ),
+            record(
+                "t4444444444444444444",
+                "d@example.com",
+                "D",
+                "2019-09-24T12:00:00.000Z"
+            ),
+        ),
+    );
+    (source, target)
+}
+
+#[test]
+fn test_sync_plan() {
+    let (source_script, target_script) = boxes();
+    let source = Client::new("y0000000000000000000").with_middleware(source_script.clone());
+    let target = Client::new("y1111111111111111111").with_middleware(target_script.clone());
+
+    let plan = source
+        .sync_plan(&target, &SyncOptions::by_key("email"))
+        .unwrap();
+    assert_eq!(
+        plan.actions,
+        vec![
+            SyncAction::Update {
+                to: Side::Target,
+                key: "b@example.com".into(),
+                id: "t2222222222222222222".into(),
+                data: json!({ "email": "b@example.com", "name": "B (new)" }),
+            },
+            SyncAction::Create {
+                to: Side::Target,
+                key: "c@example.com".into(),
+                data: json!({ "email": "c@example.com", "name": "C" }),
+            },
+            SyncAction::Create {
+                to: Side::Source,
+                key: "d@example.com".into(),
+                data: json!({ "email": "d@example.com", "name": "D" }),
+            },
+        ]
+    );
+    assert_eq!(
+        source_script.log(),
+        vec!["GET /y0000000000000000000?sort=_createdOn&skip=0&limit=1000"]
+    );
+
+    let json = serde_json::to_value(&plan).unwrap();
+    assert_eq!(json["actions"][0]["action"], "update");
+    assert_eq!(json["actions"][0]["to"], "target");
+}
+
+#[test]
+fn test_sync_apply() {
+    let (source_script, target_script) = boxes();
+    source_script
+        .push(200, r#"{"message":"Record updated."}"#)
+        .push(
+            200,
+            r#"{"_id":"s4444444444444444444","email":"d@example.com","name":"D","_createdOn":"2019-09-25T12:00:00.000Z"}"#,
+        );
+    target_script.push(500, r#"{"message":"Internal Server Error"}"#);
+    let source = Client::new("y2222222222222222222").with_middleware(source_script.clone());
+    let target = Client::new("y3333333333333333333").with_middleware(target_script.clone());
+
+    let options = SyncOptions::by_key("email").resolve_with(|_, _| Some(Side::Target));
+    let report = source.sync(&target, &options).unwrap();
+    assert_eq!(report.succeeded, vec!["b@example.com", "d@example.com"]);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, "c@example.com");
+    assert_eq!(
+        source_script.log()[1],
+        r#"PUT /y2222222222222222222/s2222222222222222222 {"email":"b@example.com","name":"B"}"#
+    );
+}
+
+#[test]
+fn test_sync_by_id_twice() {
+    let shared_old = record(
+        "x1111111111111111111",
+        "a@example.com",
+        "A",
+        "2019-09-22T12:00:00.000Z",
+    );
+    let shared_new = record(
+        "x1111111111111111111",
+        "a@example.com",
+        "A (new)",
+        "2019-09-23T12:00:00.000Z",
+    );
+    let source_only = record(
+        "x2222222222222222222",
+        "b@example.com",
+        "B",
+        "2019-09-22T12:00:00.000Z",
+    );
+    let target_only = record(
+        "x3333333333333333333",
+        "c@example.com",
+        "C",
+        "2019-09-22T12:00:00.000Z",
+    );
+    let source_script = Script::new();
+    source_script
+        .push(200, format!("[{},{}]", shared_new, source_only))
+        .push(200, format!("[{},{}]", shared_new, source_only));
+    let target_script = Script::new();
+    target_script
+        .push(200, format!("[{},{}]", shared_old, target_only))
+        .push(200, r#"{"message":"Record updated."}"#)
+        .push(200, format!("[{},{}]", shared_new, target_only));
+    let source = Client::new("y4444444444444444444").with_middleware(source_script);
+    let target = Client::new("y5555555555555555555").with_middleware(target_script.clone());
+
+    let options = SyncOptions::by_id();
+    let plan = source.sync_plan(&target, &options).unwrap();
+    assert_eq!(plan.actions.len(), 1);
+    assert_eq!(
+        plan.unmatched,
+        vec!["x2222222222222222222", "x3333333333333333333"]
+    );
+    assert!(plan.apply(&source, &target).is_ok());
+    assert_eq!(
+        target_script.log()[1],
+        r#"PUT /y5555555555555555555/x1111111111111111111 {"email":"a@example.com","name":"A (new)"}"#
+    );
+
+    // Nothing is created, so the boxes are in step after one run.
+    let plan = source.sync_plan(&target, &options).unwrap();
+    assert!(plan.is_empty());
+    assert_eq!(target_script.remaining(), 0);
+}
+
+#[test]
+fn test_sync_plan_bypasses_cache() {
+    let a = record(
+        "z1111111111111111111",
+        "a@example.com",
+        "A",
+        "2019-09-22T12:00:00.000Z",
+    );
+    let b = record(
+        "z2222222222222222222",
+        "b@example.com",
+        "B",
+        "2019-09-23T12:00:00.000Z",
+    );
+    let source_script = Script::new();
+    source_script
+        .push(200, format!("[{}]", a))
+        .push(200, format!("[{},{}]", a, b));
+    let target_script = Script::new();
+    target_script
+        .push(200, format!("[{}]", a))
+        .push(200, format!("[{}]", a));
+    let source = Client::new("y6666666666666666666")
+        .with_middleware(source_script.clone())
+        .with_cache(10, Duration::from_secs(60));
+    let target = Client::new("y7777777777777777777")
+        .with_middleware(target_script.clone())
+        .with_cache(10, Duration::from_secs(60));
+
+    let options = SyncOptions::by_key("email");
+    assert!(source.sync_plan(&target, &options).unwrap().is_empty());
+    let plan = source.sync_plan(&target, &options).unwrap();
+    assert_eq!(
+        plan.actions,
+        vec![SyncAction::Create {
+            to: Side::Target,
+            key: "b@example.com".into(),
+            data: json!({ "email": "b@example.com", "name": "B" }),
+        }]
+    );
+    assert_eq!(source_script.remaining(), 0);
+    assert_eq!(target_script.remaining(), 0);
+}
@@ -0,0 +1,125 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use common::Script;
+use jsonbox::header::HeaderValue;
+use jsonbox::{Client, Next, Request};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use tracing_core::span::Current;
+
+/// Collects every field value recorded on spans and events.
+#[derive(Clone, Default)]
+struct Recorder {
+    fields: Arc<Mutex<Vec<(String, String)>>>,
+    spans: Arc<Mutex<Vec<&'static Metadata<'static>>>>,
+    entered: Arc<Mutex<Vec<Id>>>,
+}
+
+impl Recorder {
+    fn get(&self, name: &str) -> Vec<String> {
+        let fields = self.fields.lock().unwrap();
+        fields
+            .iter()
+            .filter(|(field, _)| field == name)
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+}
+
+impl Visit for Recorder {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        let mut fields = self.fields.lock().unwrap();
+        fields.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        let mut fields = self.fields.lock().unwrap();
+        fields.push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes) -> Id {
+        span.record(&mut self.clone());
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(span.metadata());
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _: &Id, values: &Record) {
+        values.record(&mut self.clone());
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event) {
+        event.record(&mut self.clone());
+    }
+
+    fn enter(&self, span: &Id) {
+        self.entered.lock().unwrap().push(span.clone());
+    }
+
+    fn exit(&self, _: &Id) {
+        self.entered.lock().unwrap().pop();
+    }
+
+    fn current_span(&self) -> Current {
+        match self.entered.lock().unwrap().last() {
+            Some(id) => {
+                let metadata = self.spans.lock().unwrap()[id.into_u64() as usize - 1];
+                Current::new(id.clone(), metadata)
+            }
+            None => Current::none(),
+        }
+    }
+}
+
+#[test]
+fn test_span_fields() {
+    let script = Script::new();
+    script.push(
+        200,
+        r#"[{"_id":"11111111111111111111","name":"kuy","_createdOn":"2019-09-22T12:00:00.000Z"}]"#,
+    );
+    let client = Client::new("t0000000000000000000")
+        .with_middleware(|mut req: Request, next: Next| {
+            req.headers
+                .insert("x-api-key", HeaderValue::from_static("s3cr3t-api-key"));
+            next.run(req)
+        })
+        .with_middleware(script.clone());
+
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let mut query = client.read();
+        query.filter_by("name:{}", "kuy").limit(1);
+        query.run::<serde_json::Value>().unwrap();
+    });
+
+    assert_eq!(recorder.get("op"), vec!["read_by_query"]);
+    assert_eq!(recorder.get("method"), vec!["GET"]);
+    assert_eq!(recorder.get("box_id"), vec!["t0000000000000000000"]);
+    assert_eq!(
+        recorder.get("query"),
+        vec!["sort=-_createdOn&skip=0&limit=1&q=name:kuy"]
+    );
+    assert_eq!(recorder.get("status"), vec!["200"]);
+    assert_eq!(recorder.get("latency_ms").len(), 1);
+    assert_eq!(recorder.get("response_size").len(), 1);
+
+    assert_eq!(script.log().len(), 1);
+    let fields = recorder.fields.lock().unwrap();
+    assert!(fields
+        .iter()
+        .all(|(field, value)| !field.contains("x-api-key") && !value.contains("s3cr3t")));
+}
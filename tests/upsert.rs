@@ -0,0 +1,115 @@
+mod common;
+
+use common::Script;
+use jsonbox::{Client, Error, Upsert};
+use matches::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+struct User {
+    email: String,
+    name: String,
+}
+
+fn user() -> User {
+    User {
+        email: "kuy@example.com".into(),
+        name: "kuy".into(),
+    }
+}
+
+const QUERY: &str =
+    "GET /u0000000000000000000?sort=-_createdOn&skip=0&limit=1000&q=email:kuy%40example%2Ecom";
+
+#[test]
+fn test_upsert_create() {
+    let script = Script::new();
+    script.push(200, "[]").push(
+        200,
+        r#"{"_id":"11111111111111111111","email":"kuy@example.com","name":"kuy","_createdOn":"2019-09-22T12:24:37.513Z"}"#,
+    );
+    let client = Client::new("u0000000000000000000").with_middleware(script.clone());
+    let res = client
+        .upsert_by("email", "kuy@example.com", &user())
+        .unwrap();
+    assert_matches!(res, Upsert::Created(ref meta) if meta.id == "11111111111111111111");
+    assert_eq!(
+        script.log(),
+        vec![
+            QUERY,
+            r#"POST /u0000000000000000000 {"email":"kuy@example.com","name":"kuy"}"#
+        ]
+    );
+}
+
+#[test]
+fn test_upsert_update() {
+    let script = Script::new();
+    script
+        .push(
+            200,
+            r#"[{"_id":"11111111111111111111","email":"kuy@example.com","name":"old","_createdOn":"2019-09-22T12:24:37.513Z"},{"_id":"22222222222222222222","email":"KUY@example.com","name":"other","_createdOn":"2019-09-22T12:24:37.513Z"}]"#,
+        )
+        .push(200, r#"{"message":"Record updated."}"#);
+    let client = Client::new("u0000000000000000000").with_middleware(script.clone());
+    let res = client
+        .upsert_by("email", "kuy@example.com", &user())
+        .unwrap();
+    assert_matches!(res, Upsert::Updated(ref id) if id == "11111111111111111111");
+    assert_eq!(
+        script.log()[1],
+        r#"PUT /u0000000000000000000/11111111111111111111 {"email":"kuy@example.com","name":"kuy"}"#
+    );
+}
+
+#[test]
+fn test_upsert_duplicate() {
+    let script = Script::new();
+    script.push(
+        200,
+        r#"[{"_id":"11111111111111111111","email":"kuy@example.com","name":"a","_createdOn":"2019-09-22T12:24:37.513Z"},{"_id":"22222222222222222222","email":"kuy@example.com","name":"b","_createdOn":"2019-09-22T12:24:37.513Z"}]"#,
+    );
+    let client = Client::new("u0000000000000000000").with_middleware(script.clone());
+    let res = client.upsert_by("email", "kuy@example.com", &user());
+    assert_matches!(
+        res,
+        Err(Error::Duplicate { ref ids, .. })
+            if *ids == vec!["11111111111111111111", "22222222222222222222"]
+    );
+    assert_eq!(script.log().len(), 1);
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Item {
+    sku: u32,
+    name: String,
+}
+
+#[test]
+fn test_upsert_numeric_key() {
+    let script = Script::new();
+    script
+        .push(
+            200,
+            r#"[{"_id":"11111111111111111111","sku":42,"name":"old","_createdOn":"2019-09-22T12:24:37.513Z"}]"#,
+        )
+        .push(200, r#"{"message":"Record updated."}"#);
+    let client = Client::new("u1111111111111111111").with_middleware(script.clone());
+    let item = Item {
+        sku: 42,
+        name: "new".into(),
+    };
+    let res = client.upsert_by("sku", 42, &item).unwrap();
+    assert_matches!(res, Upsert::Updated(ref id) if id == "11111111111111111111");
+    assert_eq!(
+        script.log()[0],
+        "GET /u1111111111111111111?sort=-_createdOn&skip=0&limit=1000&q=sku:=42"
+    );
+}
+
+#[test]
+fn test_upsert_invalid_key() {
+    let client = Client::new("u2222222222222222222").with_middleware(Script::new());
+    let res = client.upsert_by("tags", vec!["a"], &user());
+    assert_matches!(res, Err(Error::Json { .. }));
+}
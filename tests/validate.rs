@@ -0,0 +1,79 @@
+mod common;
+
+use common::Script;
+use jsonbox::{Client, Error, Violation};
+use matches::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Data {
+    name: String,
+    count: i32,
+}
+
+fn non_negative(payload: &Value) -> Vec<Violation> {
+    match payload["count"].as_i64() {
+        Some(count) if count >= 0 => vec![],
+        _ => vec![Violation::new("/count", "must be non-negative")],
+    }
+}
+
+#[test]
+fn test_reject_before_request() {
+    let script = Script::new();
+    let client = Client::new("v0000000000000000000")
+        .with_middleware(script.clone())
+        .with_validator(non_negative);
+    let bad = Data {
+        name: "kuy".into(),
+        count: -1,
+    };
+
+    let res = client.create(&bad);
+    assert_matches!(res, Err(Error::Validation { ref violations }) if violations[0].path == "/count");
+
+    let res = client.update("11111111111111111111", &bad);
+    assert_matches!(res, Err(Error::Validation { .. }));
+
+    let good = Data {
+        name: "cargo".into(),
+        count: 1,
+    };
+    let res = client.create_bulk(&[good, bad]);
+    let err = res.unwrap_err();
+    assert_matches!(err, Error::Validation { ref violations } if violations.len() == 1 && violations[0].path == "/1/count");
+    assert_eq!(
+        err.to_string(),
+        "Validation: /1/count: must be non-negative"
+    );
+
+    assert!(script.log().is_empty());
+    assert_eq!(
+        client
+            .metrics()
+            .operation("create")
+            .unwrap()
+            .errors
+            .get("validation"),
+        Some(&1)
+    );
+}
+
+#[test]
+fn test_pass_valid_payload() {
+    let script = Script::new();
+    script.push(200, r#"{"message":"Record updated."}"#);
+    let client = Client::new("v1111111111111111111")
+        .with_middleware(script.clone())
+        .with_validator(non_negative);
+    let data = Data {
+        name: "kuy".into(),
+        count: 42,
+    };
+    assert!(client.update("11111111111111111111", &data).is_ok());
+    assert_eq!(
+        script.log(),
+        vec![r#"PUT /v1111111111111111111/11111111111111111111 {"name":"kuy","count":42}"#]
+    );
+}
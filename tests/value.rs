@@ -0,0 +1,61 @@
+use jsonbox::Client;
+use mockito::{mock, Matcher};
+use serde_json::json;
+
+#[test]
+fn test_create_value() {
+    let _m = mock("POST", "/v0000000000000000000")
+        .match_body(Matcher::Json(json!({"name":"rust","count":42})))
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"_id":"11111111111111111111","name":"rust","count":42,"_createdOn":"2019-09-22T12:24:37.513Z"}"#)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("v0000000000000000000").with_base_url(&server_url);
+    let data = json!({"_id": "ignored", "name": "rust", "count": 42});
+    let (record, meta) = client.create_value(&data).unwrap();
+    assert_eq!(record, json!({"name": "rust", "count": 42}));
+    assert_eq!(meta.id, "11111111111111111111");
+    assert_eq!(meta.created_on, "2019-09-22T12:24:37.513Z");
+}
+
+#[test]
+fn test_read_value_and_update_value() {
+    let _m1 = mock("GET", "/v1111111111111111111/11111111111111111111")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"_id":"11111111111111111111","name":"kuy","count":42,"_createdOn":"2019-09-22T12:24:37.513Z","_updatedOn":"2019-09-22T12:25:52.114Z"}"#)
+        .create();
+    let _m2 = mock("PUT", "/v1111111111111111111/11111111111111111111")
+        .match_body(Matcher::Json(json!({"name":"kuy","count":43})))
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"{"message":"Record updated."}"#)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("v1111111111111111111").with_base_url(&server_url);
+
+    let (mut record, meta) = client.read().id_value("11111111111111111111").unwrap();
+    assert_eq!(record, json!({"name": "kuy", "count": 42}));
+    assert_eq!(meta.updated_on, "2019-09-22T12:25:52.114Z");
+
+    record["count"] = json!(43);
+    record["_updatedOn"] = json!("2019-09-22T12:25:52.114Z");
+    assert!(client.update_value(&meta.id, &record).is_ok());
+}
+
+#[test]
+fn test_run_values() {
+    let _m = mock("GET", "/v2222222222222222222?sort=-_createdOn&skip=0&limit=20")
+        .with_status(200)
+        .with_header("content-type", "application/json; charset=utf-8")
+        .with_body(r#"[{"_id":"11111111111111111111","name":"kuy","_createdOn":"2019-09-23T12:24:37.513Z"},{"_id":"22222222222222222222","tags":["a"],"_createdOn":"2019-09-22T12:24:37.513Z"}]"#)
+        .create();
+    let server_url = mockito::server_url();
+    let client = Client::new("v2222222222222222222").with_base_url(&server_url);
+    let all = client.read().run_values().unwrap();
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[0].0, json!({"name": "kuy"}));
+    assert_eq!(all[1].0, json!({"tags": ["a"]}));
+    assert_eq!(all[1].1.id, "22222222222222222222");
+}
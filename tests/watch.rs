@@ -0,0 +1,233 @@
+mod common;
+
+use common::Script;
+use jsonbox::{Client, Event};
+use matches::*;
+use std::time::Duration;
+
+/// A record which was updated, so it has `_updatedOn`.
+fn record(id: &str, name: &str, created_on: &str, updated_on: &str) -> String {
+    format!(
+        r#"{{"_id":"{}","name":"{}","_createdOn":"2019-09-22T12:00:0{}.000Z","_updatedOn":"2019-09-22T12:00:0{}.000Z"}}"#,
+        id, name, created_on, updated_on
+    )
+}
+
+/// A record which was never updated, as jsonbox returns it without `_updatedOn`.
+fn created(id: &str, name: &str, created_on: &str) -> String {
+    format!(
+        r#"{{"_id":"{}","name":"{}","_createdOn":"2019-09-22T12:00:0{}.000Z"}}"#,
+        id, name, created_on
+    )
+}
+
+const A: &str = "11111111111111111111";
+const B: &str = "22222222222222222222";
+const C: &str = "33333333333333333333";
+
+#[test]
+fn test_watch_events() {
+    let script = Script::new();
+    let a = created(A, "kuy", "0");
+    let b = created(B, "Yuki", "1");
+    let b2 = record(B, "Yuki K.", "1", "5");
+    let c = created(C, "cargo", "3");
+    script
+        .push(200, format!("[{},{}]", b, a))
+        .push(200, format!("[{},{}]", b, a))
+        .push(200, format!("[{},{}]", a, b))
+        .push(200, format!("[{},{}]", b2, c))
+        .push(200, format!("[{},{}]", c, b2))
+        .push(200, format!("[{},{}]", b2, c))
+        .push(400, r#"{"message":"Invalid record Id"}"#);
+    let client = Client::new("w0000000000000000000").with_middleware(script.clone());
+
+    let mut query = client.read();
+    query.filter_by("name:{}", "*").limit(10);
+    let events: Vec<Event> = client
+        .watch(&query, Duration::from_millis(0))
+        .take(3)
+        .map(|event| event.unwrap())
+        .collect();
+
+    assert_matches!(&events[0], Event::Created(record, meta) if meta.id == C && record["name"] == "cargo");
+    assert_matches!(&events[1], Event::Updated(record, meta) if meta.id == B && record["name"] == "Yuki K.");
+    assert_matches!(&events[2], Event::Deleted(id) if id == A);
+
+    let poll = vec![
+        "GET /w0000000000000000000?sort=-_updatedOn&skip=0&limit=10&q=name:%2A",
+        "GET /w0000000000000000000?sort=-_createdOn&skip=0&limit=10&q=name:%2A",
+        "GET /w0000000000000000000?sort=_createdOn&skip=0&limit=10&q=name:%2A",
+    ];
+    let mut expected = [poll.clone(), poll].concat();
+    expected.push("GET /w0000000000000000000/11111111111111111111");
+    assert_eq!(script.log(), expected);
+}
+
+#[test]
+fn test_created_without_updated_on() {
+    let script = Script::new();
+    let a = created(A, "kuy", "1");
+    let b = record(B, "Yuki", "0", "2");
+    let b2 = record(B, "Yuki K.", "0", "4");
+    let c = created(C, "cargo", "3");
+    script
+        .push(200, format!("[{},{}]", b, a))
+        .push(200, format!("[{},{}]", a, b))
+        // Records never updated sort after updated ones, so c is past the mark here.
+        .push(200, format!("[{},{},{}]", b2, a, c))
+        .push(200, format!("[{},{},{}]", c, a, b2));
+    let client = Client::new("w4444444444444444444").with_middleware(script.clone());
+
+    let mut query = client.read();
+    query.limit(10);
+    let mut watch = client
+        .watch(&query, Duration::from_secs(60))
+        .deletions(false);
+    assert!(watch.poll().unwrap().is_empty());
+    assert_eq!(watch.high_water_mark(), Some("2019-09-22T12:00:02.000Z"));
+
+    let events = watch.poll().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_matches!(&events[0], Event::Created(record, meta) if meta.id == C && record["name"] == "cargo");
+    assert_matches!(&events[1], Event::Updated(_, meta) if meta.id == B);
+    assert_eq!(script.remaining(), 0);
+}
+
+#[test]
+fn test_poll_stops_at_high_water() {
+    let script = Script::new();
+    let a = record(A, "kuy", "0", "0");
+    let b = record(B, "Yuki", "0", "1");
+    let c = created(C, "cargo", "2");
+    script
+        .push(200, format!("[{}]", b))
+        .push(200, format!("[{}]", a))
+        .push(200, format!("[{}]", b))
+        .push(200, format!("[{}]", b))
+        .push(200, format!("[{}]", a))
+        .push(200, format!("[{}]", c))
+        .push(200, format!("[{}]", b))
+        .push(500, r#"{"message":"Internal Server Error"}"#);
+    let client = Client::new("w1111111111111111111").with_middleware(script.clone());
+
+    let mut query = client.read();
+    query.limit(1);
+    let mut watch = client
+        .watch(&query, Duration::from_secs(60))
+        .deletions(false);
+    assert!(watch.poll().unwrap().is_empty());
+    assert_eq!(watch.high_water_mark(), Some("2019-09-22T12:00:01.000Z"));
+    let events = watch.poll().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_matches!(&events[0], Event::Created(_, meta) if meta.id == C);
+    assert_eq!(watch.high_water_mark(), Some("2019-09-22T12:00:02.000Z"));
+    assert!(watch.poll().is_err());
+
+    assert_eq!(
+        script.log(),
+        vec![
+            "GET /w1111111111111111111?sort=-_updatedOn&skip=0&limit=1",
+            "GET /w1111111111111111111?sort=-_updatedOn&skip=1&limit=1",
+            "GET /w1111111111111111111?sort=-_createdOn&skip=0&limit=1",
+            "GET /w1111111111111111111?sort=-_updatedOn&skip=0&limit=1",
+            "GET /w1111111111111111111?sort=-_updatedOn&skip=1&limit=1",
+            "GET /w1111111111111111111?sort=-_createdOn&skip=0&limit=1",
+            "GET /w1111111111111111111?sort=-_createdOn&skip=1&limit=1",
+            "GET /w1111111111111111111?sort=-_updatedOn&skip=0&limit=1",
+        ]
+    );
+}
+
+#[test]
+fn test_update_between_pages() {
+    let script = Script::new();
+    let a = created(A, "kuy", "1");
+    let a2 = record(A, "kuy!", "1", "5");
+    let b = created(B, "Yuki", "2");
+    let b2 = record(B, "Yuki!", "2", "4");
+    let c = created(C, "cargo", "3");
+    script
+        // First poll: a snapshot.
+        .push(200, format!("[{},{}]", c, b))
+        .push(200, format!("[{},{}]", c, b))
+        .push(200, format!("[{},{}]", a, b))
+        .push(200, format!("[{}]", c))
+        // Second poll: b was updated before it, a is updated after the first page.
+        .push(200, format!("[{},{}]", b2, c))
+        .push(200, format!("[{}]", c))
+        .push(200, format!("[{},{}]", c, b2))
+        .push(200, format!("[{},{}]", a2, b2))
+        .push(200, format!("[{}]", c))
+        // Third poll: the update of a is caught.
+        .push(200, format!("[{},{}]", a2, b2))
+        .push(200, format!("[{}]", c))
+        .push(200, format!("[{},{}]", c, b2))
+        .push(200, format!("[{},{}]", a2, b2))
+        .push(200, format!("[{}]", c));
+    let client = Client::new("w2222222222222222222").with_middleware(script.clone());
+
+    let mut query = client.read();
+    query.limit(2);
+    let mut watch = client.watch(&query, Duration::from_secs(60));
+    assert!(watch.poll().unwrap().is_empty());
+
+    let events = watch.poll().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_matches!(&events[0], Event::Updated(_, meta) if meta.id == B);
+
+    let events = watch.poll().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_matches!(&events[0], Event::Updated(record, meta) if meta.id == A && record["name"] == "kuy!");
+
+    assert_eq!(script.remaining(), 0);
+    assert_eq!(
+        script.log()[4..9].to_vec(),
+        vec![
+            "GET /w2222222222222222222?sort=-_updatedOn&skip=0&limit=2",
+            "GET /w2222222222222222222?sort=-_updatedOn&skip=2&limit=2",
+            "GET /w2222222222222222222?sort=-_createdOn&skip=0&limit=2",
+            "GET /w2222222222222222222?sort=_createdOn&skip=0&limit=2",
+            "GET /w2222222222222222222?sort=_createdOn&skip=2&limit=2",
+        ]
+    );
+}
+
+#[test]
+fn test_deleted_record_shifts_scan() {
+    let script = Script::new();
+    let a = created(A, "kuy", "1");
+    let b = created(B, "Yuki", "2");
+    let c = created(C, "cargo", "3");
+    script
+        .push(200, format!("[{},{}]", c, b))
+        .push(200, format!("[{},{}]", c, b))
+        .push(200, format!("[{},{}]", a, b))
+        .push(200, format!("[{}]", c))
+        // a is deleted after the first page of the scan, so c moves to it and is missed.
+        .push(200, format!("[{},{}]", c, b))
+        .push(200, format!("[{},{}]", c, b))
+        .push(200, format!("[{},{}]", a, b))
+        .push(200, "[]")
+        .push(200, c.as_str())
+        .push(200, format!("[{},{}]", c, b))
+        .push(200, format!("[{},{}]", c, b))
+        .push(200, format!("[{},{}]", b, c))
+        .push(200, "[]")
+        .push(400, r#"{"message":"Invalid record Id"}"#);
+    let client = Client::new("w3333333333333333333").with_middleware(script.clone());
+
+    let mut query = client.read();
+    query.limit(2);
+    let mut watch = client.watch(&query, Duration::from_secs(60));
+    assert!(watch.poll().unwrap().is_empty());
+    assert!(watch.poll().unwrap().is_empty());
+    let events = watch.poll().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_matches!(&events[0], Event::Deleted(id) if id == A);
+
+    let log = script.log();
+    assert_eq!(log[8], "GET /w3333333333333333333/33333333333333333333");
+    assert_eq!(log[13], "GET /w3333333333333333333/11111111111111111111");
+    assert_eq!(script.remaining(), 0);
+}